@@ -0,0 +1,86 @@
+//! Compares rendering a log statement with the previous approach, which assembled the tag from
+//! intermediate strings, to the current one, which writes into a reused buffer, and measures the
+//! structured formats.
+//!
+//! Run with `cargo bench --bench render`.
+
+#[macro_use]
+extern crate criterion;
+extern crate ansi_term;
+extern crate log;
+extern crate loggerv;
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use ansi_term::Colour;
+use criterion::{black_box, Criterion};
+use log::{Level, Log, Record};
+use loggerv::{ColorChoice, Format, Logger, Output};
+
+/// Builds the tag like `create_tag` did before, with the level, the module path, and the line
+/// number, colorized with the default color of errors.
+fn create_tag(record: &Record) -> String {
+    let level_text = record.level().to_string();
+    let module_path_text = format!(" [{}]", record.module_path().unwrap_or("unknown"));
+    let line_text = match record.line() {
+        Some(l) => format!(" (line {})", l),
+        None => String::new(),
+    };
+    let mut tag = format!("{}{}{}", level_text, module_path_text, line_text);
+    tag = Colour::Fixed(9).paint(tag).to_string();
+    tag
+}
+
+/// Writes a log statement like `log` did before.
+fn previous(output: &Mutex<io::Sink>, record: &Record) {
+    writeln!(output.lock().unwrap(), "{}: {}", create_tag(record), record.args()).expect("Writing to the sink");
+}
+
+/// Calls the function with a typical log statement.
+fn with_record<F: FnOnce(&Record)>(f: F) {
+    f(&Record::builder()
+        .level(Level::Error)
+        .module_path(Some("app::net::client"))
+        .line(Some(42))
+        .args(format_args!("connection lost after {} retries", black_box(3)))
+        .build())
+}
+
+/// Creates a logger that writes errors in the format to a sink, like the previous approach.
+fn logger(format: Format) -> Logger {
+    Logger::new()
+        .color_choice(ColorChoice::Always)
+        .level(true)
+        .line_numbers(true)
+        .format(format)
+        .output(&Level::Error, Output::Writer(Arc::new(Mutex::new(io::sink()))))
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+
+    let sink = Mutex::new(io::sink());
+    group.bench_function("previous", |b| {
+        b.iter(|| with_record(|r| previous(&sink, r)))
+    });
+
+    let current = logger(Format::Text);
+    group.bench_function("current", |b| {
+        b.iter(|| with_record(|r| current.log(r)))
+    });
+
+    let json = logger(Format::Json);
+    group.bench_function("json", |b| {
+        b.iter(|| with_record(|r| json.log(r)))
+    });
+
+    let logfmt = logger(Format::Logfmt);
+    group.bench_function("logfmt", |b| {
+        b.iter(|| with_record(|r| logfmt.log(r)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
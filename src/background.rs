@@ -0,0 +1,380 @@
+//! Writing the log statements on a background thread.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+#[cfg(unix)]
+use std::sync::{Once, Weak};
+use std::thread;
+
+use super::{Output, MAX_BUFFER_CAPACITY};
+use write_error::WriteErrors;
+
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// The queues of all running workers, which are drained when the process exits.
+#[cfg(unix)]
+static QUEUES: Mutex<Vec<Weak<Queue>>> = Mutex::new(Vec::new());
+#[cfg(unix)]
+static DRAIN_AT_EXIT: Once = Once::new();
+
+/// What to do with a log statement when the queue of the background thread is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wait until the background thread makes room, so no log statement is lost.
+    Block,
+    /// Discard the new log statement.
+    DropNewest,
+    /// Discard the oldest log statement in the queue to make room for the new one.
+    DropOldest,
+}
+
+/// The configuration of writing the log statements on a background thread.
+///
+/// A log statement is formatted on the calling thread, so its timestamp and elapsed time are
+/// accurate, and then sent to a bounded queue. A dedicated thread writes the statements from the
+/// queue to their outputs, so the calling thread never waits for a slow terminal, pipe, or file,
+/// unless the queue is full and the overflow policy is `Overflow::Block`.
+///
+/// The queue is drained when the logger is flushed, e.g. with `log::logger().flush()`, and on Unix
+/// when the process exits normally, i.e. by returning from `main` or calling `std::process::exit`;
+/// elsewhere, the logger should be flushed before the process exits. Log statements that are
+/// dropped because the queue is full are counted, and a notice with the count is written before
+/// the next statement.
+///
+/// Errors of writing are handled with the `OnWriteError` policy, except that `OnWriteError::Panic`
+/// cannot panic on the background thread, so it writes a notice with the first error to stderr.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use loggerv::{Background, Overflow};
+///
+/// fn main() {
+///     loggerv::Logger::new()
+///         .background(Background::new().capacity(4096).overflow(Overflow::DropOldest))
+///         .init()
+///         .unwrap();
+///
+///     error!("This is written on a background thread");
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Background {
+    capacity: usize,
+    overflow: Overflow,
+}
+
+impl Background {
+    /// Creates the configuration with a capacity of 1024 log statements and `Overflow::Block`.
+    pub fn new() -> Background {
+        Background {
+            capacity: DEFAULT_CAPACITY,
+            overflow: Overflow::Block,
+        }
+    }
+
+    /// Sets the maximum number of log statements in the queue. A capacity of zero is treated as
+    /// one.
+    pub fn capacity(mut self, c: usize) -> Self {
+        self.capacity = c;
+        self
+    }
+
+    /// Sets what to do with a log statement when the queue is full.
+    pub fn overflow(mut self, o: Overflow) -> Self {
+        self.overflow = o;
+        self
+    }
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background::new()
+    }
+}
+
+struct Entry {
+    output: Output,
+    line: String,
+}
+
+struct State {
+    entries: VecDeque<Entry>,
+    /// The number of log statements that were dropped since the last notice.
+    dropped: u64,
+    /// The number of log statements that were queued, including the dropped ones.
+    queued: u64,
+    /// The number of log statements that were written or dropped.
+    done: u64,
+    closed: bool,
+    /// The buffers of written log statements, which are reused for the next ones.
+    free: Vec<String>,
+}
+
+struct Queue {
+    state: Mutex<State>,
+    errors: WriteErrors,
+    capacity: usize,
+    overflow: Overflow,
+    /// Signaled when a log statement is queued or the queue is closed.
+    not_empty: Condvar,
+    /// Signaled when the background thread takes log statements from the queue.
+    not_full: Condvar,
+    /// Signaled when the background thread has written log statements.
+    written: Condvar,
+}
+
+impl Queue {
+    fn new(b: &Background, errors: WriteErrors) -> Queue {
+        Queue {
+            errors,
+            state: Mutex::new(State {
+                entries: VecDeque::new(),
+                dropped: 0,
+                queued: 0,
+                done: 0,
+                closed: false,
+                free: Vec::new(),
+            }),
+            capacity: b.capacity.max(1),
+            overflow: b.overflow,
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            written: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state is only changed by complete operations, so a poisoned lock is still consistent.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, entry: Entry) {
+        let mut state = self.lock();
+        state.queued += 1;
+        while state.entries.len() >= self.capacity {
+            match self.overflow {
+                Overflow::Block => {
+                    state = self.not_full.wait(state).unwrap_or_else(|e| e.into_inner());
+                },
+                Overflow::DropNewest => {
+                    state.dropped += 1;
+                    state.done += 1;
+                    return;
+                },
+                Overflow::DropOldest => {
+                    state.entries.pop_front();
+                    state.dropped += 1;
+                    state.done += 1;
+                },
+            }
+        }
+        state.entries.push_back(entry);
+        self.not_empty.notify_one();
+    }
+
+    /// Waits until all log statements that were queued before the call are written.
+    fn drain(&self) {
+        let mut state = self.lock();
+        let target = state.queued;
+        while state.done < target && !state.closed {
+            state = self.written.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.not_empty.notify_all();
+    }
+
+    /// Writes the log statements until the queue is closed and empty.
+    fn run(&self) {
+        let mut entries = VecDeque::new();
+        loop {
+            let dropped = {
+                let mut state = self.lock();
+                while state.entries.is_empty() && !state.closed {
+                    state = self.not_empty.wait(state).unwrap_or_else(|e| e.into_inner());
+                }
+                if state.entries.is_empty() {
+                    break;
+                }
+                // The queues are swapped, so neither allocates again once it has grown.
+                mem::swap(&mut state.entries, &mut entries);
+                mem::replace(&mut state.dropped, 0)
+            };
+            self.not_full.notify_all();
+            if dropped > 0 {
+                let notice = format!("loggerv: dropped {} log statements because the queue was full\n", dropped);
+                self.errors.write(&entries[0].output, notice.as_bytes(), true);
+            }
+            for entry in &entries {
+                self.errors.write(&entry.output, entry.line.as_bytes(), true);
+            }
+            {
+                let mut state = self.lock();
+                state.done += entries.len() as u64;
+                for entry in entries.drain(..) {
+                    if state.free.len() < self.capacity && entry.line.capacity() <= MAX_BUFFER_CAPACITY {
+                        let mut line = entry.line;
+                        line.clear();
+                        state.free.push(line);
+                    }
+                }
+            }
+            self.written.notify_all();
+        }
+        self.written.notify_all();
+    }
+}
+
+/// The background thread of a prepared logger, which is stopped when the logger is dropped.
+pub(crate) struct Worker {
+    queue: Arc<Queue>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Worker {
+    pub(crate) fn spawn(b: &Background, errors: WriteErrors) -> io::Result<Worker> {
+        let queue = Arc::new(Queue::new(b, errors));
+        let thread = {
+            let queue = Arc::clone(&queue);
+            thread::Builder::new()
+                .name(String::from("loggerv-writer"))
+                .spawn(move || queue.run())?
+        };
+        #[cfg(unix)]
+        drain_queue_at_exit(&queue);
+        Ok(Worker {
+            queue,
+            thread: Some(thread),
+        })
+    }
+
+    /// Gets an empty buffer for a log statement, which is reused after the statement is written.
+    pub(crate) fn buffer(&self) -> String {
+        self.queue.lock().free.pop().unwrap_or_default()
+    }
+
+    pub(crate) fn send(&self, output: Output, line: String) {
+        self.queue.push(Entry { output, line });
+    }
+
+    /// Waits until all log statements that were sent before the call are written.
+    pub(crate) fn drain(&self) {
+        self.queue.drain();
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.queue.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for Worker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Worker")
+            .field("capacity", &self.queue.capacity)
+            .field("overflow", &self.queue.overflow)
+            .finish()
+    }
+}
+
+impl PartialEq for Worker {
+    fn eq(&self, other: &Worker) -> bool {
+        ptr::eq(self, other)
+    }
+}
+
+/// Registers the queue to be drained when the process exits.
+#[cfg(unix)]
+fn drain_queue_at_exit(queue: &Arc<Queue>) {
+    {
+        let mut queues = QUEUES.lock().unwrap_or_else(|e| e.into_inner());
+        queues.retain(|q| q.strong_count() > 0);
+        queues.push(Arc::downgrade(queue));
+    }
+    DRAIN_AT_EXIT.call_once(|| unsafe {
+        libc::atexit(drain_at_exit);
+    });
+}
+
+#[cfg(unix)]
+extern "C" fn drain_at_exit() {
+    let queues: Vec<Arc<Queue>> = QUEUES.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+    for queue in queues {
+        queue.drain();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use write_error::OnWriteError;
+
+    fn entry(output: &Output, line: &str) -> Entry {
+        Entry {
+            output: output.clone(),
+            line: String::from(line),
+        }
+    }
+
+    #[test]
+    fn worker_writes_in_order() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let output = Output::Writer(buffer.clone());
+        let worker = Worker::spawn(&Background::new().capacity(2), WriteErrors::new(OnWriteError::Panic)).unwrap();
+        for i in 0..10 {
+            worker.send(output.clone(), format!("{}\n", i));
+        }
+        worker.drain();
+        assert_eq!(String::from_utf8(buffer.lock().unwrap().clone()).unwrap(), "0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+        // The written lines are kept for reuse, up to the capacity.
+        assert_eq!(worker.queue.lock().free.len(), 2);
+        assert_eq!(worker.buffer(), "");
+    }
+
+    #[test]
+    fn drop_newest_works() {
+        let output = Output::writer(io::sink());
+        let queue = Queue::new(&Background::new().capacity(2).overflow(Overflow::DropNewest), WriteErrors::new(OnWriteError::Panic));
+        for line in &["a", "b", "c"] {
+            queue.push(entry(&output, line));
+        }
+        let state = queue.lock();
+        assert_eq!(state.entries.iter().map(|e| e.line.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(state.dropped, 1);
+    }
+
+    #[test]
+    fn drop_oldest_writes_a_notice() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let output = Output::Writer(buffer.clone());
+        let queue = Queue::new(&Background::new().capacity(2).overflow(Overflow::DropOldest), WriteErrors::new(OnWriteError::Panic));
+        for line in &["a\n", "b\n", "c\n", "d\n"] {
+            queue.push(entry(&output, line));
+        }
+        queue.close();
+        queue.run();
+        assert_eq!(
+            String::from_utf8(buffer.lock().unwrap().clone()).unwrap(),
+            "loggerv: dropped 2 log statements because the queue was full\nc\nd\n"
+        );
+        queue.drain();
+    }
+}
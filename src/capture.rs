@@ -0,0 +1,304 @@
+//! Capturing log statements in tests.
+
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+use log;
+
+use super::{Logger, Output};
+use key_values;
+
+type Records = Rc<RefCell<Vec<CapturedRecord>>>;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Records>> = const { RefCell::new(None) };
+}
+
+static INSTALL: Once = Once::new();
+
+/// If any thread has started a capture, so log statements are only looked up in the captures of
+/// the current thread once a test uses them.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// If a loggerv logger is installed, which is the only kind of logger that captures.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// A captured log statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRecord {
+    pub level: log::Level,
+    pub target: String,
+    pub module_path: Option<String>,
+    pub line: Option<u32>,
+    /// The tag as it would be written, without colors.
+    pub tag: String,
+    /// The rendered message.
+    pub message: String,
+    /// The key-value pairs, including hidden ones, with the values rendered as text.
+    pub key_values: Vec<(String, String)>,
+}
+
+/// The log statements of the current thread, captured until the capture is dropped.
+///
+/// Captures can be nested, e.g. in a helper function, and the previous capture continues when the
+/// inner one is dropped.
+#[derive(Debug)]
+pub struct Capture {
+    records: Records,
+    previous: Option<Records>,
+}
+
+impl Capture {
+    /// Gets the captured log statements, oldest first.
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        self.records.borrow().clone()
+    }
+
+    /// Gets the messages of the captured log statements with the level, oldest first.
+    pub fn messages(&self, level: log::Level) -> Vec<String> {
+        self.records.borrow().iter().filter(|r| r.level == level).map(|r| r.message.clone()).collect()
+    }
+
+    /// Determines if a log statement with the level has a message containing the text.
+    pub fn contains(&self, level: log::Level, text: &str) -> bool {
+        self.records.borrow().iter().any(|r| r.level == level && r.message.contains(text))
+    }
+
+    /// Removes the captured log statements.
+    pub fn clear(&self) {
+        self.records.borrow_mut().clear();
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        let _ = CURRENT.try_with(|c| *c.borrow_mut() = previous);
+    }
+}
+
+/// Starts capturing the log statements of the current thread.
+///
+/// A capture records the log statements of the current thread instead of writing them, so tests
+/// that run in parallel, each on its own thread, see only their own log statements. The first
+/// capture installs a logger that captures everything up to TRACE and discards the log statements
+/// of other threads. If a loggerv logger was already initialized, it captures instead, with its
+/// own levels and filters. Log statements of threads spawned by a test are not captured.
+///
+/// # Panics
+///
+/// If another kind of logger is installed, since it would never capture, and every
+/// `assert_logged!` would fail.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// #[macro_use] extern crate loggerv;
+///
+/// use log::Level;
+///
+/// fn connect() {
+///     warn!("Connection lost, retrying");
+/// }
+///
+/// fn main() {
+///     let capture = loggerv::start_capture();
+///     connect();
+///     assert_logged!(Level::Warn, contains "retry");
+///     assert_eq!(capture.records()[0].message, "Connection lost, retrying");
+/// }
+/// ```
+pub fn start_capture() -> Capture {
+    INSTALL.call_once(|| {
+        let sink = Output::writer(io::sink());
+        let mut logger = Logger::new().max_level(log::Level::Trace).no_colors();
+        for l in &[log::Level::Error, log::Level::Warn, log::Level::Info, log::Level::Debug, log::Level::Trace] {
+            logger = logger.output(l, sink.clone());
+        }
+        // Another logger may already be installed, which keeps its maximum level.
+        if log::set_boxed_logger(Box::new(logger.prepare())).is_ok() {
+            log::set_max_level(log::LevelFilter::Trace);
+            installed();
+        }
+    });
+    assert!(INSTALLED.load(Ordering::SeqCst), "loggerv::start_capture requires that no logger other than loggerv is installed");
+    begin()
+}
+
+/// Marks that a loggerv logger is installed.
+pub(crate) fn installed() {
+    INSTALLED.store(true, Ordering::SeqCst);
+}
+
+/// Starts capturing without installing a logger, e.g. for the tests of this crate, which must not
+/// interfere with initializing a logger.
+fn begin() -> Capture {
+    ACTIVE.store(true, Ordering::Relaxed);
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let previous = CURRENT.with(|c| c.borrow_mut().replace(records.clone()));
+    Capture { records, previous }
+}
+
+/// Determines if any thread has started a capture, which is checked before `record`.
+#[inline]
+pub(crate) fn active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Records the log statement if the current thread is capturing.
+pub(crate) fn record(logger: &Logger, record: &log::Record) -> bool {
+    CURRENT.try_with(|c| match *c.borrow() {
+        Some(ref records) => {
+            let mut tag = String::new();
+            logger.write_tag(&mut tag, record, false);
+            records.borrow_mut().push(CapturedRecord {
+                level: record.level(),
+                target: String::from(record.target()),
+                module_path: record.module_path().map(String::from),
+                line: record.line(),
+                tag,
+                message: record.args().to_string(),
+                key_values: key_values::pairs(record).into_iter().map(|p| (p.key, p.value)).collect(),
+            });
+            true
+        },
+        None => false,
+    }).unwrap_or(false)
+}
+
+/// How `assert_logged!` matches the message.
+#[doc(hidden)]
+pub enum MessageMatch<'a> {
+    Contains(&'a str),
+    Equals(&'a str),
+}
+
+#[doc(hidden)]
+pub fn assert_logged(level: log::Level, m: MessageMatch, expected: bool) {
+    let records = CURRENT.with(|c| c.borrow().as_ref().map(|r| r.borrow().clone()))
+        .expect("assert_logged! requires a capture, see `loggerv::start_capture`");
+    let found = records.iter().any(|r| r.level == level && match m {
+        MessageMatch::Contains(t) => r.message.contains(t),
+        MessageMatch::Equals(t) => r.message == t,
+    });
+    if found != expected {
+        let description = match m {
+            MessageMatch::Contains(t) => format!("containing {:?}", t),
+            MessageMatch::Equals(t) => format!("equal to {:?}", t),
+        };
+        let captured: Vec<String> = records.iter()
+            .map(|r| format!("  {} {}: {}", r.level, r.module_path.as_deref().unwrap_or("unknown"), r.message))
+            .collect();
+        panic!(
+            "expected {} log statement with level {} {}, captured {}:\n{}",
+            if expected { "a" } else { "no" },
+            level,
+            description,
+            records.len(),
+            captured.join("\n")
+        );
+    }
+}
+
+/// Asserts that the current capture has a log statement with the level and a matching message.
+///
+/// The message either contains a text, e.g. `assert_logged!(Level::Warn, contains "retry")`, or
+/// equals it, e.g. `assert_logged!(Level::Info, "Connected")`. The panic message lists the
+/// captured log statements. See `start_capture`.
+#[macro_export]
+macro_rules! assert_logged {
+    ($level:expr, contains $text:expr) => {
+        $crate::assert_logged($level, $crate::MessageMatch::Contains($text), true)
+    };
+    ($level:expr, $text:expr) => {
+        $crate::assert_logged($level, $crate::MessageMatch::Equals($text), true)
+    };
+}
+
+/// Asserts that the current capture has no log statement with the level and a matching message.
+///
+/// The syntax is the same as `assert_logged!`.
+#[macro_export]
+macro_rules! assert_not_logged {
+    ($level:expr, contains $text:expr) => {
+        $crate::assert_logged($level, $crate::MessageMatch::Contains($text), false)
+    };
+    ($level:expr, $text:expr) => {
+        $crate::assert_logged($level, $crate::MessageMatch::Equals($text), false)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Log;
+
+    fn warn(logger: &Logger, message: &str) {
+        logger.log(&log::Record::builder()
+            .level(log::Level::Warn)
+            .module_path(Some("app::net"))
+            .line(Some(3))
+            .args(format_args!("{}", message))
+            .build());
+    }
+
+    #[test]
+    fn capture_works() {
+        let logger = Logger::new().level(true).color_choice(::ColorChoice::Always);
+        let capture = begin();
+        warn(&logger, "retrying");
+        assert_eq!(capture.records(), vec![CapturedRecord {
+            level: log::Level::Warn,
+            target: String::from(""),
+            module_path: Some(String::from("app::net")),
+            line: Some(3),
+            tag: String::from("WARN [app::net]"),
+            message: String::from("retrying"),
+            key_values: Vec::new(),
+        }]);
+        assert!(capture.contains(log::Level::Warn, "retry"));
+        assert_eq!(capture.messages(log::Level::Error), Vec::<String>::new());
+        assert_logged!(log::Level::Warn, contains "retry");
+        assert_logged!(log::Level::Warn, "retrying");
+        assert_not_logged!(log::Level::Error, contains "retry");
+        capture.clear();
+        assert!(capture.records().is_empty());
+    }
+
+    #[test]
+    fn start_works() {
+        // Completing the installation without installing the logger keeps `init` working in the
+        // other tests, which install a loggerv logger.
+        INSTALL.call_once(|| {});
+        installed();
+        let capture = start_capture();
+        assert!(active());
+        warn(&Logger::new(), "retrying");
+        assert_eq!(capture.messages(log::Level::Warn), vec![String::from("retrying")]);
+    }
+
+    #[test]
+    fn captures_nest() {
+        let logger = Logger::new();
+        let outer = begin();
+        {
+            let inner = begin();
+            warn(&logger, "inner");
+            assert_eq!(inner.records().len(), 1);
+        }
+        warn(&logger, "outer");
+        assert_eq!(outer.messages(log::Level::Warn), vec![String::from("outer")]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a log statement with level ERROR containing \"retry\", captured 1:\n  WARN app::net: retrying")]
+    fn assert_logged_lists_the_records() {
+        let _capture = begin();
+        warn(&Logger::new(), "retrying");
+        assert_logged!(log::Level::Error, contains "retry");
+    }
+}
@@ -0,0 +1,363 @@
+//! Deciding whether and how to colorize the output, and with which colors.
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use ansi_term::Colour;
+
+/// When to colorize the log statements.
+///
+/// With `Auto`, the output is colorized if it is a terminal, following the
+/// [`NO_COLOR`](https://no-color.org) and
+/// [`CLICOLOR`/`CLICOLOR_FORCE`](https://bixense.com/clicolors/) conventions:
+///
+/// 1. If `CLICOLOR_FORCE` is set and not `0`, the output is colorized, even if it is not a
+///    terminal, e.g. when piping to `less -R`.
+/// 2. Otherwise, if `NO_COLOR` is set and not empty, the output is not colorized.
+/// 3. Otherwise, if `CLICOLOR` is `0`, the output is not colorized.
+/// 4. Otherwise, the output is colorized if it is a terminal.
+///
+/// A writer output is never a terminal, so it is only colorized with `CLICOLOR_FORCE`. The
+/// environment is read when the logger is initialized. `Always` and `Never` ignore the
+/// environment, e.g. for a `--color` argument that overrides it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Decides if an output is colorized, based on whether it is a terminal.
+    pub fn resolve(self, is_terminal: bool) -> bool {
+        self.resolve_with(is_terminal, |name| env::var(name).ok())
+    }
+
+    pub(crate) fn resolve_with<F>(self, is_terminal: bool, var: F) -> bool
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                if var("CLICOLOR_FORCE").is_some_and(|v| v != "0") {
+                    return true;
+                }
+                let no_color = var("NO_COLOR").is_some_and(|v| !v.is_empty());
+                let clicolor_off = var("CLICOLOR").is_some_and(|v| v == "0");
+                is_terminal && !no_color && !clicolor_off
+            },
+        }
+    }
+}
+
+/// A color of the log statements, which is downgraded to what the terminal supports.
+///
+/// A color is parsed from a name, e.g. `red` or `bright-red`, a number in the 256-color palette,
+/// e.g. `208`, or a 24-bit RGB hex code, e.g. `#ff8800`. The names are `black`, `red`, `green`,
+/// `yellow`, `blue`, `magenta` (or `purple`), `cyan`, and `white`, optionally prefixed with
+/// `bright-`, where `grey` (or `gray`) is the same as `bright-black`.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate loggerv;
+///
+/// use loggerv::Color;
+///
+/// fn main() {
+///     assert_eq!("bright-red".parse(), Ok(Color::Fixed(9)));
+///     assert_eq!("208".parse(), Ok(Color::Fixed(208)));
+///     assert_eq!("#ff8800".parse(), Ok(Color::Rgb(0xff, 0x88, 0x00)));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    /// A color of the 256-color palette, where 0 to 15 are the basic and bright colors.
+    Fixed(u8),
+    /// A 24-bit color.
+    Rgb(u8, u8, u8),
+}
+
+const BASIC_NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+/// The colors of the first 16 entries of the palette, as xterm shows them by default.
+const BASIC_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+    (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+    (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+
+/// The levels of each component in the 6x6x6 color cube of the palette, from 16 to 231.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl Color {
+    /// Converts the color to the closest one the terminal supports.
+    ///
+    /// The first 16 entries of the palette and the named colors are written as they are, since
+    /// every terminal supports them; only RGB colors and the rest of the palette are downgraded.
+    pub(crate) fn to_colour(self, depth: ColorDepth) -> Colour {
+        match (self, depth) {
+            (Color::Rgb(r, g, b), ColorDepth::TrueColor) => Colour::RGB(r, g, b),
+            (Color::Rgb(r, g, b), ColorDepth::Ansi256) => Colour::Fixed(rgb_to_fixed(r, g, b)),
+            (Color::Rgb(r, g, b), ColorDepth::Ansi16) => basic(nearest_basic(r, g, b)),
+            (Color::Fixed(n), ColorDepth::Ansi16) if n >= 16 => {
+                let (r, g, b) = fixed_to_rgb(n);
+                basic(nearest_basic(r, g, b))
+            },
+            (Color::Fixed(n), _) => Colour::Fixed(n),
+            (Color::Black, _) => Colour::Black,
+            (Color::Red, _) => Colour::Red,
+            (Color::Green, _) => Colour::Green,
+            (Color::Yellow, _) => Colour::Yellow,
+            (Color::Blue, _) => Colour::Blue,
+            (Color::Magenta, _) => Colour::Purple,
+            (Color::Cyan, _) => Colour::Cyan,
+            (Color::White, _) => Colour::White,
+        }
+    }
+}
+
+impl From<Colour> for Color {
+    fn from(c: Colour) -> Color {
+        match c {
+            Colour::Black => Color::Black,
+            Colour::Red => Color::Red,
+            Colour::Green => Color::Green,
+            Colour::Yellow => Color::Yellow,
+            Colour::Blue => Color::Blue,
+            Colour::Purple => Color::Magenta,
+            Colour::Cyan => Color::Cyan,
+            Colour::White => Color::White,
+            Colour::Fixed(n) => Color::Fixed(n),
+            Colour::RGB(r, g, b) => Color::Rgb(r, g, b),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Color, ParseColorError> {
+        let name = s.trim().to_lowercase().replace('_', "-");
+        let err = || ParseColorError { color: String::from(s.trim()) };
+        if let Some(hex) = name.strip_prefix('#') {
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(err());
+            }
+            let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err());
+            return Ok(Color::Rgb(component(0)?, component(2)?, component(4)?));
+        }
+        if let Ok(n) = name.parse() {
+            return Ok(Color::Fixed(n));
+        }
+        let (bright, base) = match name.strip_prefix("bright-") {
+            Some(base) => (true, base),
+            None => (false, name.as_str()),
+        };
+        let index = match base {
+            "grey" | "gray" if !bright => return Ok(Color::Fixed(8)),
+            "purple" => 5,
+            _ => BASIC_NAMES.iter().position(|n| *n == base).ok_or_else(err)?,
+        };
+        Ok(if bright { Color::Fixed(index as u8 + 8) } else { basic_color(index) })
+    }
+}
+
+/// The error of parsing an invalid color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError {
+    color: String,
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid color `{}`, expected a name like `red` or `bright-red`, a number from 0 to 255, or `#rrggbb`",
+            self.color
+        )
+    }
+}
+
+impl Error for ParseColorError {}
+
+/// The number of colors a terminal supports.
+///
+/// The depth is detected from the `COLORTERM` and `TERM` environment variables: `COLORTERM` of
+/// `truecolor` or `24bit`, or a `TERM` ending in `-direct`, means 24-bit colors, a `TERM`
+/// containing `256color` means the 256-color palette, and anything else means the 16 basic and
+/// bright colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Detects the depth of the terminal from the environment.
+    pub fn detect() -> ColorDepth {
+        ColorDepth::detect_with(|name| env::var(name).ok())
+    }
+
+    fn detect_with<F>(var: F) -> ColorDepth
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let colorterm = var("COLORTERM").unwrap_or_default();
+        let term = var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" || term.ends_with("-direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+fn basic_color(index: usize) -> Color {
+    [
+        Color::Black, Color::Red, Color::Green, Color::Yellow,
+        Color::Blue, Color::Magenta, Color::Cyan, Color::White,
+    ][index]
+}
+
+/// Converts an index of the first 16 entries of the palette, keeping the basic colors as such.
+fn basic(index: usize) -> Colour {
+    if index < 8 {
+        basic_color(index).to_colour(ColorDepth::Ansi16)
+    } else {
+        Colour::Fixed(index as u8)
+    }
+}
+
+fn nearest_basic(r: u8, g: u8, b: u8) -> usize {
+    let distance = |&(r2, g2, b2): &(u8, u8, u8)| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(r, r2) + d(g, g2) + d(b, b2)
+    };
+    (0..BASIC_RGB.len()).min_by_key(|&i| distance(&BASIC_RGB[i])).unwrap_or(0)
+}
+
+/// Finds the closest entry of the color cube or the grey ramp of the palette.
+fn rgb_to_fixed(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        return match r {
+            0..=7 => 16,
+            249..=255 => 231,
+            v => 232 + ((v - 3) / 10).min(23),
+        };
+    }
+    let level = |v: u8| {
+        CUBE_LEVELS.iter()
+            .enumerate()
+            .min_by_key(|&(_, l)| (i16::from(*l) - i16::from(v)).abs())
+            .map_or(0, |(i, _)| i as u8)
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+fn fixed_to_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => BASIC_RGB[n as usize],
+        16..=231 => {
+            let i = n - 16;
+            (CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+        },
+        _ => {
+            let v = 8 + (n - 232) * 10;
+            (v, v, v)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(choice: ColorChoice, is_terminal: bool, vars: &[(&str, &str)]) -> bool {
+        choice.resolve_with(is_terminal, |name| {
+            vars.iter().find(|v| v.0 == name).map(|v| String::from(v.1))
+        })
+    }
+
+    #[test]
+    fn auto_follows_the_terminal() {
+        assert!(resolve(ColorChoice::Auto, true, &[]));
+        assert!(!resolve(ColorChoice::Auto, false, &[]));
+    }
+
+    #[test]
+    fn auto_follows_the_environment() {
+        assert!(!resolve(ColorChoice::Auto, true, &[("NO_COLOR", "1")]));
+        assert!(resolve(ColorChoice::Auto, true, &[("NO_COLOR", "")]));
+        assert!(!resolve(ColorChoice::Auto, true, &[("CLICOLOR", "0")]));
+        assert!(resolve(ColorChoice::Auto, true, &[("CLICOLOR", "1")]));
+        assert!(!resolve(ColorChoice::Auto, false, &[("CLICOLOR", "1")]));
+        assert!(resolve(ColorChoice::Auto, false, &[("CLICOLOR_FORCE", "1")]));
+        assert!(!resolve(ColorChoice::Auto, false, &[("CLICOLOR_FORCE", "0")]));
+        assert!(resolve(ColorChoice::Auto, false, &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]));
+    }
+
+    #[test]
+    fn always_and_never_ignore_the_environment() {
+        assert!(resolve(ColorChoice::Always, false, &[("NO_COLOR", "1")]));
+        assert!(!resolve(ColorChoice::Never, true, &[("CLICOLOR_FORCE", "1")]));
+    }
+
+    #[test]
+    fn parse_works() {
+        assert_eq!("Red".parse(), Ok(Color::Red));
+        assert_eq!("purple".parse(), Ok(Color::Magenta));
+        assert_eq!("bright-red".parse(), Ok(Color::Fixed(9)));
+        assert_eq!("bright_white".parse(), Ok(Color::Fixed(15)));
+        assert_eq!("grey".parse(), Ok(Color::Fixed(8)));
+        assert_eq!(" 208 ".parse(), Ok(Color::Fixed(208)));
+        assert_eq!("#FF8800".parse(), Ok(Color::Rgb(255, 136, 0)));
+        assert!("256".parse::<Color>().is_err());
+        assert!("reddish".parse::<Color>().is_err());
+        assert!("bright-grey".parse::<Color>().is_err());
+        assert!("#ff880".parse::<Color>().is_err());
+        assert!("#+f8800".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn downgrade_works() {
+        let orange = Color::Rgb(255, 136, 0);
+        assert_eq!(orange.to_colour(ColorDepth::TrueColor), Colour::RGB(255, 136, 0));
+        assert_eq!(orange.to_colour(ColorDepth::Ansi256), Colour::Fixed(208));
+        assert_eq!(orange.to_colour(ColorDepth::Ansi16), Colour::Yellow);
+        assert_eq!(Color::Rgb(128, 128, 128).to_colour(ColorDepth::Ansi256), Colour::Fixed(244));
+        assert_eq!(Color::Rgb(200, 0, 0).to_colour(ColorDepth::Ansi16), Colour::Red);
+        assert_eq!(Color::Fixed(196).to_colour(ColorDepth::Ansi16), Colour::Fixed(9));
+        assert_eq!(Color::Fixed(9).to_colour(ColorDepth::Ansi16), Colour::Fixed(9));
+        assert_eq!(Color::Fixed(8).to_colour(ColorDepth::Ansi16), Colour::Fixed(8));
+        assert_eq!(Color::Magenta.to_colour(ColorDepth::Ansi16), Colour::Purple);
+    }
+
+    #[test]
+    fn detect_works() {
+        let detect = |vars: &[(&str, &str)]| {
+            ColorDepth::detect_with(|name| vars.iter().find(|v| v.0 == name).map(|v| String::from(v.1)))
+        };
+        assert_eq!(detect(&[]), ColorDepth::Ansi16);
+        assert_eq!(detect(&[("TERM", "xterm")]), ColorDepth::Ansi16);
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), ColorDepth::Ansi256);
+        assert_eq!(detect(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]), ColorDepth::TrueColor);
+        assert_eq!(detect(&[("COLORTERM", "24bit")]), ColorDepth::TrueColor);
+    }
+}
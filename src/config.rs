@@ -0,0 +1,625 @@
+//! A deserializable configuration of a logger, e.g. from a section of an application's config file.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use log;
+use serde::Deserialize;
+use toml;
+
+use super::{Background, Color, ColorChoice, Directive, Elapsed, FilePath, Format, KeyValues, Logger, OnWriteError, Output, Overflow, RotatingFile, Style, Theme};
+use timestamp::{Clock, Precision, Timestamp};
+
+const LEVELS: &str = "error, warn, info, debug, or trace";
+const DIRECTIVE_LEVELS: &str = "`module=level` or `level` with a level of off, error, warn, info, debug, or trace";
+
+/// The configuration of a logger, which mirrors the builder methods of `Logger`.
+///
+/// Every field is optional and leaves the default of the logger unchanged if missing. It is
+/// usually deserialized as a section of an application's own config file, but it can also be read
+/// from a TOML document with `from_toml`. The values are validated when the logger is built, with
+/// errors that name the invalid key.
+///
+/// ```toml
+/// verbosity = 1
+/// base_level = "warn"
+/// directives = "hyper=warn,myapp::db=trace"
+/// module_path_filters = ["myapp"]
+/// separator = " | "
+/// colors = "auto"
+/// include_level = true
+/// timestamp = { format = "rfc3339", clock = "local", precision = "millis" }
+/// key_values = { hide = ["password"], order = ["request_id"], key_style = "dimmed" }
+///
+/// [error]
+/// color = "red"
+/// message_style = "bold red"
+/// output = "stderr"
+///
+/// [info]
+/// output = { path = "/var/log/myapp.log", max_size = 10485760, keep = 3 }
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use loggerv::LoggerConfig;
+///
+/// fn main() {
+///     let config = LoggerConfig::from_toml("verbosity = 1\ninclude_level = true").unwrap();
+///     loggerv::Logger::from_config(&config)
+///         .unwrap()
+///         .init()
+///         .unwrap();
+///
+///     info!("This is printed with the level");
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggerConfig {
+    /// The maximum level, see `Logger::max_level`. It takes precedence over the verbosity.
+    pub level: Option<String>,
+    /// The verbosity, see `Logger::verbosity`.
+    pub verbosity: Option<u64>,
+    /// The level at a verbosity of zero, see `Logger::base_level`.
+    pub base_level: Option<String>,
+    /// A comma-separated list of directives, see `Logger::directives`.
+    pub directives: Option<String>,
+    /// The module path filters, see `Logger::module_path_filters`.
+    pub module_path_filters: Option<Vec<String>>,
+    /// The separator, see `Logger::separator`.
+    pub separator: Option<String>,
+    /// When to colorize, one of `auto`, `always`, or `never`, see `Logger::color_choice`.
+    pub colors: Option<String>,
+    /// The name of a built-in or registered theme, see `Theme`. The settings of the levels are
+    /// applied on top of it.
+    pub theme: Option<String>,
+    /// See `Logger::level`.
+    pub include_level: Option<bool>,
+    /// See `Logger::line_numbers`.
+    pub include_line_numbers: Option<bool>,
+    /// See `Logger::module_path`.
+    pub include_module_path: Option<bool>,
+    /// See `Logger::hostname`.
+    pub include_hostname: Option<bool>,
+    /// See `Logger::pid`.
+    pub include_pid: Option<bool>,
+    /// See `Logger::thread`.
+    pub include_thread: Option<bool>,
+    /// See `Logger::thread_width`.
+    pub thread_width: Option<usize>,
+    /// The path of the source file, one of `full`, `crate_relative`, or `basename`, see
+    /// `Logger::file`.
+    pub file: Option<String>,
+    /// See `Logger::compact_location`.
+    pub compact_location: Option<bool>,
+    /// The format, one of `text`, `json`, or `logfmt`, see `Logger::format`.
+    pub format: Option<String>,
+    /// A template, see `Logger::template`. It takes precedence over the format.
+    pub template: Option<String>,
+    /// The timestamp, see `Logger::timestamp`.
+    pub timestamp: Option<TimestampConfig>,
+    /// The elapsed time, one of `since_start` or `since_previous`, see `Logger::elapsed`.
+    pub elapsed: Option<String>,
+    /// The key-value pairs, see `Logger::key_values`.
+    pub key_values: Option<KeyValuesConfig>,
+    /// The name of an environment variable, see `Logger::env`.
+    pub env: Option<String>,
+    /// Writing on a background thread, see `Logger::background`.
+    pub background: Option<BackgroundConfig>,
+    /// What to do when writing fails, one of `panic`, `ignore`, or `silence`, or `stderr` or
+    /// `stdout` to fall back to, see `Logger::on_write_error`.
+    pub on_write_error: Option<String>,
+    /// The settings of the ERROR level.
+    pub error: Option<LevelConfig>,
+    /// The settings of the WARN level.
+    pub warn: Option<LevelConfig>,
+    /// The settings of the INFO level.
+    pub info: Option<LevelConfig>,
+    /// The settings of the DEBUG level.
+    pub debug: Option<LevelConfig>,
+    /// The settings of the TRACE level.
+    pub trace: Option<LevelConfig>,
+}
+
+/// The configuration of a timestamp, see `Timestamp`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimestampConfig {
+    /// One of `rfc3339` or `time`, or a `strftime`-like pattern containing a `%`. The default is
+    /// `rfc3339`.
+    pub format: Option<String>,
+    /// One of `utc` or `local`. The default is `utc`.
+    pub clock: Option<String>,
+    /// One of `seconds`, `millis`, `micros`, or `nanos`. The default is `seconds`.
+    pub precision: Option<String>,
+}
+
+/// The configuration of the key-value pairs, see `KeyValues`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyValuesConfig {
+    /// The keys of the pairs that are not written.
+    pub hide: Option<Vec<String>>,
+    /// The keys that are written first, in this order.
+    pub order: Option<Vec<String>>,
+    /// The style of all keys, e.g. `dimmed`. See `Style`.
+    pub key_style: Option<String>,
+    /// The styles of the values by key, e.g. `{ user = "cyan" }`. See `Style`.
+    pub value_styles: Option<BTreeMap<String, String>>,
+}
+
+/// The configuration of a background thread, see `Background`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackgroundConfig {
+    /// The maximum number of log statements in the queue. The default is 1024.
+    pub capacity: Option<usize>,
+    /// One of `block`, `drop_newest`, or `drop_oldest`. The default is `block`.
+    pub overflow: Option<String>,
+}
+
+/// The configuration of a level.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LevelConfig {
+    /// The color of the tag, either a name, e.g. `bright-red`, a number in the 256-color palette,
+    /// e.g. `208`, or a 24-bit hex code, e.g. `#ff8800`. See `Color`.
+    pub color: Option<String>,
+    /// The style of the tag, e.g. `bold red on black`, applied before `color`. See `Style`.
+    pub style: Option<String>,
+    /// The style of the message, e.g. `italic`. See `Logger::message_style`.
+    pub message_style: Option<String>,
+    /// The output, see `Logger::output`.
+    pub output: Option<OutputConfig>,
+}
+
+/// The configuration of an output.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum OutputConfig {
+    /// Either `stderr` or `stdout`.
+    Stream(String),
+    /// A file, which is shared by all levels with the same path.
+    File(FileConfig),
+}
+
+/// The configuration of a file output, see `RotatingFile`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub path: PathBuf,
+    pub max_size: Option<u64>,
+    pub daily: Option<bool>,
+    pub keep: Option<usize>,
+}
+
+impl LoggerConfig {
+    /// Reads the configuration from a TOML document.
+    pub fn from_toml(s: &str) -> Result<LoggerConfig, ConfigError> {
+        toml::from_str(s).map_err(|e| ConfigError::Toml(e.to_string()))
+    }
+
+    /// Applies the configuration on top of the logger.
+    pub fn apply(&self, mut logger: Logger) -> Result<Logger, ConfigError> {
+        if let Some(ref l) = self.base_level {
+            logger = logger.base_level(parse_level("base_level", l)?);
+        }
+        if let Some(v) = self.verbosity {
+            logger = logger.verbosity(v);
+        }
+        if let Some(ref l) = self.level {
+            logger = logger.max_level(parse_level("level", l)?);
+        }
+        if let Some(ref d) = self.directives {
+            // Unlike an environment variable, a config file is expected to be valid.
+            for entry in d.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let directive = entry.parse::<Directive>()
+                    .map_err(|_| ConfigError::invalid("directives", entry, DIRECTIVE_LEVELS))?;
+                logger = logger.directive(directive.module(), directive.level());
+            }
+        }
+        if let Some(ref f) = self.module_path_filters {
+            logger = logger.module_path_filters(f.clone());
+        }
+        if let Some(ref s) = self.separator {
+            logger = logger.separator(s);
+        }
+        if let Some(ref c) = self.colors {
+            logger = logger.color_choice(match c.as_str() {
+                "auto" => ColorChoice::Auto,
+                "always" => ColorChoice::Always,
+                "never" => ColorChoice::Never,
+                _ => return Err(ConfigError::invalid("colors", c, "auto, always, or never")),
+            });
+        }
+        if let Some(ref t) = self.theme {
+            let theme = Theme::named(t).ok_or_else(|| {
+                ConfigError::invalid(
+                    "theme",
+                    t,
+                    "default, solarized, monochrome-bold, high-contrast, light-background, or a registered theme",
+                )
+            })?;
+            logger = logger.theme(&theme);
+        }
+        if let Some(i) = self.include_level {
+            logger = logger.level(i);
+        }
+        if let Some(i) = self.include_line_numbers {
+            logger = logger.line_numbers(i);
+        }
+        if let Some(i) = self.include_module_path {
+            logger = logger.module_path(i);
+        }
+        if let Some(i) = self.include_hostname {
+            logger = logger.hostname(i);
+        }
+        if let Some(i) = self.include_pid {
+            logger = logger.pid(i);
+        }
+        if let Some(i) = self.include_thread {
+            logger = logger.thread(i);
+        }
+        if let Some(w) = self.thread_width {
+            logger = logger.thread_width(w);
+        }
+        if let Some(ref f) = self.file {
+            logger = logger.file(match f.as_str() {
+                "full" => FilePath::Full,
+                "crate_relative" => FilePath::CrateRelative,
+                "basename" => FilePath::Basename,
+                _ => return Err(ConfigError::invalid("file", f, "full, crate_relative, or basename")),
+            });
+        }
+        if let Some(c) = self.compact_location {
+            logger = logger.compact_location(c);
+        }
+        if let Some(ref f) = self.format {
+            logger = logger.format(match f.as_str() {
+                "text" => Format::Text,
+                "json" => Format::Json,
+                "logfmt" => Format::Logfmt,
+                _ => return Err(ConfigError::invalid("format", f, "text, json, or logfmt")),
+            });
+        }
+        if let Some(ref t) = self.template {
+            logger = logger.template(t);
+        }
+        if let Some(ref t) = self.timestamp {
+            logger = logger.timestamp(t.to_timestamp()?);
+        }
+        if let Some(ref e) = self.elapsed {
+            logger = logger.elapsed(match e.as_str() {
+                "since_start" => Elapsed::SinceStart,
+                "since_previous" => Elapsed::SincePrevious,
+                _ => return Err(ConfigError::invalid("elapsed", e, "since_start or since_previous")),
+            });
+        }
+        if let Some(ref k) = self.key_values {
+            logger = logger.key_values(k.to_key_values()?);
+        }
+        if let Some(ref e) = self.env {
+            logger = logger.env(e);
+        }
+        if let Some(ref b) = self.background {
+            logger = logger.background(b.to_background()?);
+        }
+        if let Some(ref o) = self.on_write_error {
+            logger = logger.on_write_error(match o.as_str() {
+                "panic" => OnWriteError::Panic,
+                "ignore" => OnWriteError::Ignore,
+                "silence" => OnWriteError::Silence,
+                "stderr" => OnWriteError::Fallback(Output::Stderr),
+                "stdout" => OnWriteError::Fallback(Output::Stdout),
+                _ => return Err(ConfigError::invalid("on_write_error", o, "panic, ignore, silence, stderr, or stdout")),
+            });
+        }
+        let mut files = HashMap::new();
+        let levels = [
+            ("error", log::Level::Error, &self.error),
+            ("warn", log::Level::Warn, &self.warn),
+            ("info", log::Level::Info, &self.info),
+            ("debug", log::Level::Debug, &self.debug),
+            ("trace", log::Level::Trace, &self.trace),
+        ];
+        for &(name, level, config) in &levels {
+            if let Some(ref config) = *config {
+                logger = config.apply(name, &level, logger, &mut files)?;
+            }
+        }
+        Ok(logger)
+    }
+}
+
+impl KeyValuesConfig {
+    fn to_key_values(&self) -> Result<KeyValues, ConfigError> {
+        let mut k = KeyValues::new();
+        for key in self.hide.iter().flatten() {
+            k = k.hide(key.as_str());
+        }
+        if let Some(ref o) = self.order {
+            k = k.order(o.iter().map(String::as_str));
+        }
+        if let Some(ref s) = self.key_style {
+            k = k.key_style(parse_style("key_values.key_style", s)?);
+        }
+        for (key, s) in self.value_styles.iter().flatten() {
+            k = k.value_style(key.as_str(), parse_style(&format!("key_values.value_styles.{}", key), s)?);
+        }
+        Ok(k)
+    }
+}
+
+impl BackgroundConfig {
+    fn to_background(&self) -> Result<Background, ConfigError> {
+        let mut b = Background::new();
+        if let Some(c) = self.capacity {
+            b = b.capacity(c);
+        }
+        if let Some(ref o) = self.overflow {
+            b = b.overflow(match o.as_str() {
+                "block" => Overflow::Block,
+                "drop_newest" => Overflow::DropNewest,
+                "drop_oldest" => Overflow::DropOldest,
+                _ => return Err(ConfigError::invalid("background.overflow", o, "block, drop_newest, or drop_oldest")),
+            });
+        }
+        Ok(b)
+    }
+}
+
+impl TimestampConfig {
+    fn to_timestamp(&self) -> Result<Timestamp, ConfigError> {
+        let mut t = match self.format.as_deref() {
+            None | Some("rfc3339") => Timestamp::rfc3339(),
+            Some("time") => Timestamp::time(),
+            Some(p) if p.contains('%') => Timestamp::custom(p),
+            Some(p) => {
+                return Err(ConfigError::invalid("timestamp.format", p, "rfc3339, time, or a pattern with a %"));
+            },
+        };
+        if let Some(ref c) = self.clock {
+            t = t.clock(match c.as_str() {
+                "utc" => Clock::Utc,
+                "local" => Clock::Local,
+                _ => return Err(ConfigError::invalid("timestamp.clock", c, "utc or local")),
+            });
+        }
+        if let Some(ref p) = self.precision {
+            t = t.precision(match p.as_str() {
+                "seconds" => Precision::Seconds,
+                "millis" => Precision::Millis,
+                "micros" => Precision::Micros,
+                "nanos" => Precision::Nanos,
+                _ => return Err(ConfigError::invalid("timestamp.precision", p, "seconds, millis, micros, or nanos")),
+            });
+        }
+        Ok(t)
+    }
+}
+
+fn parse_style(key: &str, s: &str) -> Result<Style, ConfigError> {
+    s.parse().map_err(|_| ConfigError::invalid(key, s, "attributes and colors like `bold red on black`"))
+}
+
+impl LevelConfig {
+    fn apply(
+        &self,
+        name: &str,
+        level: &log::Level,
+        mut logger: Logger,
+        files: &mut HashMap<PathBuf, Output>,
+    ) -> Result<Logger, ConfigError> {
+        if let Some(ref s) = self.style {
+            logger = logger.style(level, parse_style(&format!("{}.style", name), s)?);
+        }
+        if let Some(ref s) = self.message_style {
+            logger = logger.message_style(level, parse_style(&format!("{}.message_style", name), s)?);
+        }
+        if let Some(ref c) = self.color {
+            let color = c.parse::<Color>().map_err(|_| {
+                ConfigError::invalid(&format!("{}.color", name), c, "a color name, a number from 0 to 255, or #rrggbb")
+            })?;
+            logger = logger.color(level, color);
+        }
+        match self.output {
+            Some(OutputConfig::Stream(ref s)) => {
+                logger = logger.output(level, match s.as_str() {
+                    "stderr" => Output::Stderr,
+                    "stdout" => Output::Stdout,
+                    _ => return Err(ConfigError::invalid(&format!("{}.output", name), s, "stderr, stdout, or a file")),
+                });
+            },
+            Some(OutputConfig::File(ref f)) => {
+                let output = match files.get(&f.path) {
+                    Some(output) => output.clone(),
+                    None => {
+                        let output = Output::writer(f.open()?);
+                        files.insert(f.path.clone(), output.clone());
+                        output
+                    },
+                };
+                logger = logger.output(level, output);
+            },
+            None => {},
+        }
+        Ok(logger)
+    }
+}
+
+impl FileConfig {
+    fn open(&self) -> Result<RotatingFile, ConfigError> {
+        let mut file = RotatingFile::new(&self.path);
+        if let Some(s) = self.max_size {
+            file = file.max_size(s);
+        }
+        if let Some(d) = self.daily {
+            file = file.daily(d);
+        }
+        if let Some(k) = self.keep {
+            file = file.keep(k);
+        }
+        file.open().map_err(|e| ConfigError::Io(self.path.clone(), e))
+    }
+}
+
+fn parse_level(key: &str, value: &str) -> Result<log::Level, ConfigError> {
+    value.parse().map_err(|_| ConfigError::invalid(key, value, LEVELS))
+}
+
+/// The error of an invalid configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A value is invalid.
+    Invalid {
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+    /// A file output could not be opened.
+    Io(PathBuf, io::Error),
+    /// A TOML document could not be read.
+    Toml(String),
+}
+
+impl ConfigError {
+    fn invalid(key: &str, value: &str, expected: &'static str) -> ConfigError {
+        ConfigError::Invalid {
+            key: String::from(key),
+            value: String::from(value),
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Invalid { ref key, ref value, expected } => {
+                write!(f, "invalid value `{}` for `{}`, expected {}", value, key, expected)
+            },
+            ConfigError::Io(ref path, ref e) => write!(f, "cannot open `{}`: {}", path.display(), e),
+            ConfigError::Toml(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use write_error::WriteErrors;
+
+    #[test]
+    fn from_toml_works() {
+        let config = LoggerConfig::from_toml(r#"
+            verbosity = 2
+            base_level = "error"
+            directives = "hyper=warn"
+            module_path_filters = ["app"]
+            separator = " | "
+            colors = "never"
+            theme = "solarized"
+            include_level = true
+            include_line_numbers = true
+            include_module_path = false
+            include_hostname = true
+            include_pid = true
+            include_thread = true
+            thread_width = 10
+            file = "crate_relative"
+            compact_location = true
+            format = "logfmt"
+            timestamp = { format = "time", clock = "local", precision = "millis" }
+            elapsed = "since_previous"
+            key_values = { hide = ["token"], order = ["id"], key_style = "dimmed", value_styles = { user = "cyan" } }
+            background = { capacity = 64, overflow = "drop_oldest" }
+            on_write_error = "stderr"
+
+            [error]
+            color = "purple"
+            style = "bold"
+            message_style = "italic red"
+            output = "stdout"
+        "#).unwrap();
+        let logger = Logger::from_config(&config).unwrap();
+        assert_eq!(logger.verbosity, Some(2));
+        assert_eq!(logger.offset, 0);
+        assert_eq!(logger.directives.len(), 1);
+        assert_eq!(logger.module_path_filters, vec![String::from("app")]);
+        assert_eq!(logger.separator, " | ");
+        assert_eq!(logger.color_choice, ColorChoice::Never);
+        assert!(logger.include_level && logger.include_line_numbers && !logger.include_module_path);
+        assert!(logger.include_hostname && logger.include_pid && logger.include_thread);
+        assert_eq!(logger.thread_width, 10);
+        assert_eq!(logger.file_path, Some(FilePath::CrateRelative));
+        assert!(logger.compact_location);
+        assert_eq!(logger.format, Format::Logfmt);
+        assert_eq!(logger.timestamp, Some(Timestamp::time().local().precision(Precision::Millis)));
+        assert_eq!(logger.elapsed, Some(Elapsed::SincePrevious));
+        assert_eq!(
+            logger.key_values,
+            KeyValues::new().hide("token").order(vec!["id"]).key_style(Style::new().dimmed()).value_style("user", Color::Cyan)
+        );
+        assert_eq!(logger.background, Some(Background::new().capacity(64).overflow(Overflow::DropOldest)));
+        assert_eq!(logger.write_errors, WriteErrors::new(OnWriteError::Fallback(Output::Stderr)));
+        assert_eq!(logger.error.style, Style::new().fg(Color::Magenta).bold());
+        assert_eq!(logger.error.message_style, Style::new().fg(Color::Red).italic());
+        assert_eq!(logger.warn.style, Theme::named("solarized").unwrap().style_for(&log::Level::Warn));
+        assert_eq!(logger.error.output, Output::Stdout);
+    }
+
+    #[test]
+    fn value_styles_are_ordered() {
+        let toml = "key_values = { value_styles = { user = \"cyan\", id = \"red\", peer = \"blue\", host = \"green\" } }";
+        let key_values = || Logger::from_config(&LoggerConfig::from_toml(toml).unwrap()).unwrap().key_values;
+        assert_eq!(key_values(), ::KeyValues::new()
+            .value_style("host", Color::Green)
+            .value_style("id", Color::Red)
+            .value_style("peer", Color::Blue)
+            .value_style("user", Color::Cyan));
+    }
+
+    #[test]
+    fn files_are_shared() {
+        let path = ::std::env::temp_dir().join(format!("loggerv-config-{}.log", ::std::process::id()));
+        let config = LoggerConfig::from_toml(&format!(
+            "info = {{ output = {{ path = {:?} }} }}\ndebug = {{ output = {{ path = {:?}, keep = 1 }} }}",
+            path, path
+        )).unwrap();
+        let logger = Logger::from_config(&config).unwrap();
+        assert_eq!(logger.info.output, logger.debug.output);
+        assert_ne!(logger.info.output, Output::Stderr);
+    }
+
+    #[test]
+    fn errors_name_the_key() {
+        let error = |s: &str| Logger::from_config(&LoggerConfig::from_toml(s).unwrap()).unwrap_err().to_string();
+        assert_eq!(error("level = \"verbose\""), "invalid value `verbose` for `level`, expected error, warn, info, debug, or trace");
+        assert_eq!(error("[warn]\ncolor = \"reddish\""), "invalid value `reddish` for `warn.color`, expected a color name, a number from 0 to 255, or #rrggbb");
+        assert_eq!(error("[warn]\nstyle = \"blinking\""), "invalid value `blinking` for `warn.style`, expected attributes and colors like `bold red on black`");
+        assert_eq!(error("[warn]\noutput = \"stdrr\""), "invalid value `stdrr` for `warn.output`, expected stderr, stdout, or a file");
+        assert!(error("theme = \"sepia\"").starts_with("invalid value `sepia` for `theme`, expected default, solarized"));
+        assert_eq!(
+            error("key_values = { value_styles = { user = \"glowing\" } }"),
+            "invalid value `glowing` for `key_values.value_styles.user`, expected attributes and colors like `bold red on black`"
+        );
+        assert_eq!(error("file = \"short\""), "invalid value `short` for `file`, expected full, crate_relative, or basename");
+        assert_eq!(
+            error("directives = \"app=debug, hyper=loud\""),
+            "invalid value `hyper=loud` for `directives`, expected `module=level` or `level` with a level of off, error, warn, \
+             info, debug, or trace"
+        );
+        assert_eq!(error("colors = \"yes\""), "invalid value `yes` for `colors`, expected auto, always, or never");
+        assert!(LoggerConfig::from_toml("colours = \"auto\"").unwrap_err().to_string().contains("colours"));
+    }
+}
@@ -0,0 +1,72 @@
+//! Trimming the path of the source file of a log statement.
+
+/// How the path of the source file of a log statement is written.
+///
+/// The path is the one that `file!()` returns, i.e. relative to the directory that the compiler
+/// ran in for the crates of a workspace, and absolute for dependencies and generated code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilePath {
+    /// The path as is, e.g. `/home/me/.cargo/registry/src/serde-1.0.0/src/de/mod.rs`.
+    Full,
+    /// The path relative to the root of its crate, e.g. `src/de/mod.rs`, i.e. starting with the
+    /// last `src` directory, or else the last `tests`, `examples`, or `benches` directory. A path
+    /// without one of them, e.g. of code generated into `OUT_DIR` and added with `include!`, is
+    /// written as is.
+    CrateRelative,
+    /// Only the name of the file, e.g. `mod.rs`.
+    Basename,
+}
+
+/// The directories at the root of a crate that contain the source files of other targets than the
+/// library and the binaries.
+const TARGET_DIRECTORIES: [&str; 3] = ["tests", "examples", "benches"];
+
+impl FilePath {
+    /// Trims the path, which may use either slash or backslash as separator.
+    pub(crate) fn trim(self, path: &str) -> &str {
+        match self {
+            FilePath::Full => path,
+            FilePath::CrateRelative => {
+                // A module may be named like a target directory, e.g. `src/tests/mod.rs`, so `src`
+                // takes precedence.
+                let mut src = None;
+                let mut target = None;
+                let mut component = 0;
+                for (i, c) in path.char_indices() {
+                    if c == '/' || c == '\\' {
+                        match &path[component..i] {
+                            "src" => src = Some(component),
+                            d if TARGET_DIRECTORIES.contains(&d) => target = Some(component),
+                            _ => {},
+                        }
+                        component = i + 1;
+                    }
+                }
+                &path[src.or(target).unwrap_or(0)..]
+            },
+            FilePath::Basename => match path.rfind(['/', '\\']) {
+                Some(i) => &path[i + 1..],
+                None => path,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trim_works() {
+        let dependency = "/home/me/.cargo/registry/src/index/serde-1.0.0/src/de/mod.rs";
+        assert_eq!(FilePath::Full.trim(dependency), dependency);
+        assert_eq!(FilePath::CrateRelative.trim(dependency), "src/de/mod.rs");
+        assert_eq!(FilePath::CrateRelative.trim("crates\\app\\tests\\cli.rs"), "tests\\cli.rs");
+        assert_eq!(FilePath::CrateRelative.trim("src/main.rs"), "src/main.rs");
+        assert_eq!(FilePath::CrateRelative.trim("app/src/tests/mod.rs"), "src/tests/mod.rs");
+        assert_eq!(FilePath::CrateRelative.trim("/target/debug/build/app-1/out/gen.rs"), "/target/debug/build/app-1/out/gen.rs");
+        assert_eq!(FilePath::Basename.trim(dependency), "mod.rs");
+        assert_eq!(FilePath::Basename.trim("C:\\app\\src\\lib.rs"), "lib.rs");
+        assert_eq!(FilePath::Basename.trim("lib.rs"), "lib.rs");
+    }
+}
@@ -0,0 +1,162 @@
+//! Per-module level directives, e.g. `myapp=debug,hyper=warn,myapp::db=trace`.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use log::LevelFilter;
+
+/// The maximum level for the log statements of a module and its submodules.
+///
+/// A directive is written as `module=level`, e.g. `myapp::db=trace`, or as a bare level, e.g.
+/// `debug`, which applies to all modules without a more specific directive. The level is one of
+/// `off`, `error`, `warn`, `info`, `debug`, or `trace`, in any case.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate log;
+/// extern crate loggerv;
+///
+/// use log::LevelFilter;
+/// use loggerv::Directive;
+///
+/// fn main() {
+///     let directive: Directive = "myapp::db=trace".parse().unwrap();
+///     assert_eq!(directive.module(), "myapp::db");
+///     assert_eq!(directive.level(), LevelFilter::Trace);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    module: String,
+    level: LevelFilter,
+}
+
+impl Directive {
+    /// Creates a directive for the module and its submodules. An empty module applies to all
+    /// modules.
+    pub fn new<S: Into<String>>(module: S, level: LevelFilter) -> Directive {
+        Directive {
+            module: module.into(),
+            level,
+        }
+    }
+
+    /// Gets the module.
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    /// Gets the level.
+    pub fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    /// Determines if the directive applies to the module path.
+    ///
+    /// A directive applies to the module itself and its submodules, but not to other modules
+    /// that only share a prefix, i.e. `app` applies to `app::net` but not to `application`.
+    pub fn matches(&self, path: &str) -> bool {
+        self.module.is_empty()
+            || (path.starts_with(&self.module)
+                && (path.len() == self.module.len() || path[self.module.len()..].starts_with("::")))
+    }
+}
+
+impl FromStr for Directive {
+    type Err = ParseDirectiveError;
+
+    fn from_str(s: &str) -> Result<Directive, ParseDirectiveError> {
+        let s = s.trim();
+        let (module, level) = match s.find('=') {
+            Some(i) => (s[..i].trim(), s[i + 1..].trim()),
+            None => ("", s),
+        };
+        if s.starts_with('=') {
+            return Err(ParseDirectiveError { directive: String::from(s) });
+        }
+        level.parse()
+            .map(|level| Directive::new(module, level))
+            .map_err(|_| ParseDirectiveError { directive: String::from(s) })
+    }
+}
+
+/// The error of parsing an invalid directive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDirectiveError {
+    directive: String,
+}
+
+impl fmt::Display for ParseDirectiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid directive `{}`, expected `module=level` or `level` with a level of off, error, warn, info, debug, or trace",
+            self.directive
+        )
+    }
+}
+
+impl Error for ParseDirectiveError {}
+
+/// Parses a comma-separated list of directives, ignoring the invalid ones.
+pub(crate) fn parse_directives(spec: &str) -> Vec<Directive> {
+    spec.split(',')
+        .filter(|d| !d.trim().is_empty())
+        .filter_map(|d| d.parse().ok())
+        .collect()
+}
+
+/// Finds the level of the most specific directive that applies to the module path.
+///
+/// The most specific directive is the one with the longest module. If several directives have the
+/// same module, the last one wins.
+pub(crate) fn level_for(directives: &[Directive], path: &str) -> Option<LevelFilter> {
+    let mut best: Option<&Directive> = None;
+    for d in directives.iter().filter(|d| d.matches(path)) {
+        if best.map_or(true, |b| d.module.len() >= b.module.len()) {
+            best = Some(d);
+        }
+    }
+    best.map(|d| d.level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_works() {
+        assert_eq!("app=debug".parse(), Ok(Directive::new("app", LevelFilter::Debug)));
+        assert_eq!(" app::db = TRACE ".parse(), Ok(Directive::new("app::db", LevelFilter::Trace)));
+        assert_eq!("warn".parse(), Ok(Directive::new("", LevelFilter::Warn)));
+        assert_eq!("app=off".parse(), Ok(Directive::new("app", LevelFilter::Off)));
+        assert!("app=verbose".parse::<Directive>().is_err());
+        assert!("=debug".parse::<Directive>().is_err());
+        assert!("app".parse::<Directive>().is_err());
+    }
+
+    #[test]
+    fn parse_directives_works() {
+        assert_eq!(
+            parse_directives("myapp=debug,, hyper=warn,bogus=loud,myapp::db=trace"),
+            vec![
+                Directive::new("myapp", LevelFilter::Debug),
+                Directive::new("hyper", LevelFilter::Warn),
+                Directive::new("myapp::db", LevelFilter::Trace),
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_match_wins() {
+        let directives = parse_directives("info,myapp=debug,myapp::db=trace,hyper=warn");
+        assert_eq!(level_for(&directives, "myapp::db::pool"), Some(LevelFilter::Trace));
+        assert_eq!(level_for(&directives, "myapp::net"), Some(LevelFilter::Debug));
+        assert_eq!(level_for(&directives, "myapplication"), Some(LevelFilter::Info));
+        assert_eq!(level_for(&directives, "hyper"), Some(LevelFilter::Warn));
+        assert_eq!(level_for(&parse_directives("hyper=warn"), "myapp"), None);
+        assert_eq!(level_for(&parse_directives("app=warn,app=trace"), "app"), Some(LevelFilter::Trace));
+    }
+}
@@ -0,0 +1,355 @@
+//! Structured encodings of a log statement.
+
+use std::fmt::Write;
+
+use log;
+
+use super::{write_thread_name, Logger, MODULE_PATH_UNKNOWN};
+
+/// The encoding of each log statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// The colorized tag, the separator, and the message. This is the default.
+    Text,
+    /// One JSON object per line, with the `level`, `target`, `module_path`, `file`, `line`, and
+    /// `message` fields, where the `file` is trimmed if the path is included in the tag, a
+    /// `timestamp` field if a timestamp is configured, `hostname`, `pid`, and `thread` fields if
+    /// they are included in the tag, and a `fields` object with the key-value pairs if there are
+    /// any.
+    Json,
+    /// One line of `key=value` pairs per log statement, e.g. `level=warn module=app::net line=42
+    /// msg="connection lost"`. The `level`, `host`, `pid`, `thread`, `module`, `file`, and `line`
+    /// keys are included based on the same settings as the tag, and a `time` key if a timestamp is
+    /// configured. The key-value pairs follow the message, with characters that are not allowed in
+    /// keys, e.g. spaces, replaced with `_`.
+    Logfmt,
+    /// A user-defined layout, e.g. `"{time} {level:>5} {module}:{line} | {msg}"`, which replaces
+    /// the tag and the separator. See `Logger::template` for the syntax.
+    Template(String),
+}
+
+/// Appends a log statement encoded as a JSON object, without a trailing newline.
+pub(crate) fn write_json(s: &mut String, logger: &Logger, record: &log::Record) {
+    s.push('{');
+    if let Some(ref t) = logger.timestamp {
+        s.push_str("\"timestamp\":");
+        let start = s.len();
+        t.write_now(s);
+        json_string_from(s, start);
+        s.push(',');
+    }
+    s.push_str("\"level\":");
+    json_string(s, record.level().as_str());
+    if logger.include_hostname {
+        s.push_str(",\"hostname\":");
+        json_string(s, logger.resolved_hostname());
+    }
+    if logger.include_pid {
+        write!(s, ",\"pid\":{}", ::std::process::id()).expect("Writing to a string");
+    }
+    if logger.include_thread {
+        s.push_str(",\"thread\":");
+        let start = s.len();
+        write_thread_name(s);
+        json_string_from(s, start);
+    }
+    s.push_str(",\"target\":");
+    json_string(s, record.target());
+    s.push_str(",\"module_path\":");
+    json_optional_string(s, record.module_path());
+    s.push_str(",\"file\":");
+    match logger.file_path {
+        Some(p) => json_optional_string(s, record.file().map(|f| p.trim(f))),
+        None => json_optional_string(s, record.file()),
+    }
+    s.push_str(",\"line\":");
+    match record.line() {
+        Some(l) => write!(s, "{}", l).expect("Writing to a string"),
+        None => s.push_str("null"),
+    }
+    s.push_str(",\"message\":");
+    let start = s.len();
+    write!(s, "{}", record.args()).expect("Writing to a string");
+    json_string_from(s, start);
+    let pairs = logger.key_values.collect(record);
+    if !pairs.is_empty() {
+        s.push_str(",\"fields\":{");
+        for (i, pair) in pairs.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            json_string(s, &pair.key);
+            s.push(':');
+            if pair.primitive {
+                s.push_str(&pair.value);
+            } else {
+                json_string(s, &pair.value);
+            }
+        }
+        s.push('}');
+    }
+    s.push('}');
+}
+
+fn json_optional_string(s: &mut String, v: Option<&str>) {
+    match v {
+        Some(v) => json_string(s, v),
+        None => s.push_str("null"),
+    }
+}
+
+/// Appends a log statement encoded as logfmt, without a trailing newline.
+pub(crate) fn write_logfmt(s: &mut String, logger: &Logger, record: &log::Record) {
+    let start = s.len();
+    if let Some(ref t) = logger.timestamp {
+        logfmt_key(s, start, "time");
+        let value = s.len();
+        t.write_now(s);
+        logfmt_value_from(s, value);
+    }
+    if logger.include_level {
+        let level = match record.level() {
+            log::Level::Error => "error",
+            log::Level::Warn => "warn",
+            log::Level::Info => "info",
+            log::Level::Debug => "debug",
+            log::Level::Trace => "trace",
+        };
+        logfmt_pair(s, start, "level", level);
+    }
+    if logger.include_hostname {
+        logfmt_pair(s, start, "host", logger.resolved_hostname());
+    }
+    if logger.include_pid {
+        logfmt_key(s, start, "pid");
+        write!(s, "{}", ::std::process::id()).expect("Writing to a string");
+    }
+    if logger.include_thread {
+        logfmt_key(s, start, "thread");
+        let value = s.len();
+        write_thread_name(s);
+        logfmt_value_from(s, value);
+    }
+    if logger.include_module_path {
+        logfmt_pair(s, start, "module", record.module_path().unwrap_or(MODULE_PATH_UNKNOWN));
+    }
+    if let Some(f) = logger.select_file(record) {
+        logfmt_pair(s, start, "file", f);
+    }
+    if logger.include_line_numbers {
+        if let Some(l) = record.line() {
+            logfmt_key(s, start, "line");
+            write!(s, "{}", l).expect("Writing to a string");
+        }
+    }
+    logfmt_key(s, start, "msg");
+    let value = s.len();
+    write!(s, "{}", record.args()).expect("Writing to a string");
+    logfmt_value_from(s, value);
+    for pair in logger.key_values.collect(record) {
+        logfmt_pair(s, start, &pair.key, &pair.value);
+    }
+}
+
+/// Writes a `key=value` pair, preceded by a space unless it is the first pair of the log statement
+/// that starts at the index.
+fn logfmt_pair(s: &mut String, start: usize, key: &str, value: &str) {
+    logfmt_key(s, start, key);
+    logfmt_value(s, value);
+}
+
+/// Writes the `key=` of a pair, preceded by a space unless it is the first pair of the log
+/// statement that starts at the index.
+///
+/// Keys cannot be quoted, so the characters that would need quotes are replaced with `_`, e.g. in
+/// the keys of the key-value pairs of a log statement.
+fn logfmt_key(s: &mut String, start: usize, key: &str) {
+    if s.len() > start {
+        s.push(' ');
+    }
+    if key.is_empty() {
+        s.push('_');
+    }
+    s.extend(key.chars().map(|c| if needs_quotes_char(c) { '_' } else { c }));
+    s.push('=');
+}
+
+/// Writes a logfmt value, which is quoted if it is empty or contains a space, an equal sign, a
+/// quote, a backslash, or a control character.
+pub(crate) fn logfmt_value(s: &mut String, v: &str) {
+    if !needs_quotes(v) {
+        s.push_str(v);
+        return;
+    }
+    // The escapes of logfmt are the same as those of JSON.
+    json_string(s, v);
+}
+
+/// Quotes the value that was written from the index on, if logfmt requires it.
+fn logfmt_value_from(s: &mut String, start: usize) {
+    if needs_quotes(&s[start..]) {
+        json_string_from(s, start);
+    }
+}
+
+fn needs_quotes(v: &str) -> bool {
+    v.is_empty() || v.chars().any(needs_quotes_char)
+}
+
+fn needs_quotes_char(c: char) -> bool {
+    c <= ' ' || c == '=' || c == '"' || c == '\\' || c.is_control()
+}
+
+/// Writes a quoted and escaped JSON string.
+pub(crate) fn json_string(s: &mut String, v: &str) {
+    s.push('"');
+    for c in v.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(s, "\\u{:04x}", c as u32).expect("Writing to a string"),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+}
+
+/// Quotes and escapes the text that was written from the index on as a JSON string. The text is
+/// only copied if it has to be escaped.
+fn json_string_from(s: &mut String, start: usize) {
+    if s[start..].chars().any(needs_escape) {
+        let raw = s.split_off(start);
+        json_string(s, &raw);
+    } else {
+        s.insert(start, '"');
+        s.push('"');
+    }
+}
+
+fn needs_escape(c: char) -> bool {
+    c == '"' || c == '\\' || (c as u32) < 0x20
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(logger: &Logger, record: &log::Record) -> String {
+        let mut s = String::new();
+        write_json(&mut s, logger, record);
+        s
+    }
+
+    fn logfmt(logger: &Logger, record: &log::Record) -> String {
+        let mut s = String::new();
+        write_logfmt(&mut s, logger, record);
+        s
+    }
+
+    fn record_with<'a>(args: ::std::fmt::Arguments<'a>) -> log::Record<'a> {
+        log::Record::builder()
+            .level(log::Level::Warn)
+            .target("app")
+            .module_path(Some("app::net"))
+            .file(Some("src/net.rs"))
+            .line(Some(42))
+            .args(args)
+            .build()
+    }
+
+    #[test]
+    fn json_works() {
+        let record = record_with(format_args!("said \"hi\"\n\tand \u{1} left\\"));
+        assert_eq!(
+            json(&Logger::new(), &record),
+            "{\"level\":\"WARN\",\"target\":\"app\",\"module_path\":\"app::net\",\"file\":\"src/net.rs\",\
+             \"line\":42,\"message\":\"said \\\"hi\\\"\\n\\tand \\u0001 left\\\\\"}"
+        );
+    }
+
+    #[test]
+    fn json_missing_fields_are_null() {
+        let record = log::Record::builder()
+            .level(log::Level::Info)
+            .target("app")
+            .args(format_args!("ok"))
+            .build();
+        assert_eq!(
+            json(&Logger::new(), &record),
+            "{\"level\":\"INFO\",\"target\":\"app\",\"module_path\":null,\"file\":null,\"line\":null,\"message\":\"ok\"}"
+        );
+    }
+
+    #[test]
+    fn key_values_work() {
+        let kvs: [(&str, log::kv::Value); 3] = [
+            ("user", log::kv::Value::from("alice smith")),
+            ("admin", log::kv::Value::from(false)),
+            ("ratio", log::kv::Value::from(0.5)),
+        ];
+        let record = log::Record::builder()
+            .level(log::Level::Info)
+            .target("app")
+            .args(format_args!("login"))
+            .key_values(&kvs)
+            .build();
+        let logger = Logger::new().key_values(::KeyValues::new().hide("ratio"));
+        assert_eq!(
+            json(&logger, &record),
+            "{\"level\":\"INFO\",\"target\":\"app\",\"module_path\":null,\"file\":null,\"line\":null,\"message\":\"login\",\
+             \"fields\":{\"user\":\"alice smith\",\"admin\":false}}"
+        );
+        let logger = logger.module_path(false);
+        assert_eq!(logfmt(&logger, &record), "msg=login user=\"alice smith\" admin=false");
+        let kvs: [(&str, log::kv::Value); 2] = [("user name", log::kv::Value::from("alice")), ("a=\"b\"", log::kv::Value::from(1))];
+        let record = log::Record::builder().args(format_args!("login")).key_values(&kvs).build();
+        assert_eq!(logfmt(&logger, &record), "msg=login user_name=alice a__b_=1");
+    }
+
+    #[test]
+    fn thread_works() {
+        let logger = Logger::new().thread(true).module_path(false);
+        let name = ::std::thread::current().name().unwrap().to_owned();
+        let record = record_with(format_args!("ok"));
+        assert!(json(&logger, &record).starts_with(&format!("{{\"level\":\"WARN\",\"thread\":\"{}\",\"target\"", name)));
+        assert_eq!(logfmt(&logger, &record), format!("thread={} msg=ok", name));
+    }
+
+    #[test]
+    fn hostname_and_pid_work() {
+        let logger = Logger::new().hostname(true).pid(true).module_path(false).prepare();
+        let hostname = logger.hostname.clone().unwrap();
+        let pid = ::std::process::id();
+        let record = record_with(format_args!("ok"));
+        let mut expected = String::new();
+        json_string(&mut expected, &hostname);
+        assert!(json(&logger, &record).starts_with(&format!("{{\"level\":\"WARN\",\"hostname\":{},\"pid\":{},", expected, pid)));
+        expected.clear();
+        logfmt_value(&mut expected, &hostname);
+        assert_eq!(logfmt(&logger, &record), format!("host={} pid={} msg=ok", expected, pid));
+    }
+
+    #[test]
+    fn logfmt_works() {
+        let logger = Logger::new().level(true).line_numbers(true);
+        let record = record_with(format_args!("connection lost"));
+        assert_eq!(logfmt(&logger, &record), "level=warn module=app::net line=42 msg=\"connection lost\"");
+        let logger = Logger::new().level(true).file(::FilePath::Basename);
+        let record = record_with(format_args!("connection lost"));
+        assert_eq!(logfmt(&logger, &record), "level=warn module=app::net file=net.rs msg=\"connection lost\"");
+        assert!(json(&logger, &record).contains("\"file\":\"net.rs\""));
+        let logger = Logger::new().module_path(false);
+        let record = record_with(format_args!("a=\"b\"\n"));
+        assert_eq!(logfmt(&logger, &record), "msg=\"a=\\\"b\\\"\\n\"");
+        let record = record_with(format_args!(""));
+        assert_eq!(logfmt(&logger, &record), "msg=\"\"");
+        let record = record_with(format_args!("ok"));
+        assert_eq!(logfmt(&logger, &record), "msg=ok");
+        let mut s = String::from("INFO ");
+        write_logfmt(&mut s, &logger, &record);
+        assert_eq!(s, "INFO msg=ok");
+    }
+}
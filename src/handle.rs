@@ -0,0 +1,226 @@
+//! Changing the configuration of an initialized logger.
+
+use std::mem;
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+
+use log;
+
+use super::Logger;
+use filter::{self, Directive};
+
+/// A handle to change the level and filters of the logger after initialization.
+///
+/// The handle is created by `Logger::init_with_handle` and can be cloned and sent to other
+/// threads, e.g. to a signal handler or a thread reading key strokes. Every change also updates
+/// `log::set_max_level`, so the logging macros keep discarding disabled log statements cheaply.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// fn main() {
+///     let handle = loggerv::Logger::new()
+///         .init_with_handle()
+///         .unwrap();
+///
+///     debug!("This is not printed");
+///     handle.set_max_level(log::Level::Debug);
+///     debug!("This is printed");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoggerHandle {
+    /// The active logger, which is only locked to take or change it, never while writing.
+    logger: Arc<RwLock<Arc<Logger>>>,
+}
+
+impl LoggerHandle {
+    pub(crate) fn new(logger: Logger) -> LoggerHandle {
+        LoggerHandle {
+            logger: Arc::new(RwLock::new(Arc::new(logger))),
+        }
+    }
+
+    /// Gets the level for modules without a directive.
+    pub fn max_level(&self) -> log::Level {
+        self.read().level
+    }
+
+    /// Sets the level for modules without a directive.
+    ///
+    /// A directive for all modules, i.e. a bare level from the `directives` method or an
+    /// environment variable, is removed so the level takes effect. Directives for specific
+    /// modules are kept.
+    pub fn set_max_level(&self, l: log::Level) {
+        self.update(|logger| {
+            logger.level = l;
+            logger.directives.retain(|d| !d.module().is_empty());
+        });
+    }
+
+    /// Gets the directives.
+    pub fn directives(&self) -> Vec<Directive> {
+        self.read().directives.clone()
+    }
+
+    /// Adds a directive for the module and its submodules. See `Logger::directive`.
+    pub fn add_directive<S: Into<String>>(&self, module: S, level: log::LevelFilter) {
+        let directive = Directive::new(module, level);
+        self.update(|logger| logger.directives.push(directive));
+    }
+
+    /// Replaces all directives with the ones from a comma-separated list. See
+    /// `Logger::directives`.
+    pub fn set_directives(&self, spec: &str) {
+        let directives = filter::parse_directives(spec);
+        self.update(|logger| logger.directives = directives);
+    }
+
+    /// Removes all directives.
+    pub fn clear_directives(&self) {
+        self.update(|logger| logger.directives.clear());
+    }
+
+    /// Gets the module path filters.
+    pub fn module_path_filters(&self) -> Vec<String> {
+        self.read().module_path_filters.clone()
+    }
+
+    /// Replaces the module path filters. See `Logger::module_path_filters`.
+    pub fn set_module_path_filters(&self, filters: Vec<String>) {
+        self.update(|logger| logger.module_path_filters = filters);
+    }
+
+    /// Replaces the whole configuration with the logger, as if it had been initialized instead.
+    ///
+    /// A log statement that is being written finishes with the old configuration; all later ones
+    /// use the new one. The elapsed time keeps counting from the original initialization.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     let handle = loggerv::Logger::new()
+    ///         .init_with_handle()
+    ///         .unwrap();
+    ///
+    ///     handle.replace(loggerv::Logger::new().level(true).verbosity(1));
+    ///     info!("This is printed with the level");
+    /// }
+    /// ```
+    pub fn replace(&self, logger: Logger) {
+        let mut logger = logger.prepare();
+        let old = {
+            let mut active = self.write();
+            logger.stopwatch = active.stopwatch.clone();
+            let old = mem::replace(&mut *active, Arc::new(logger));
+            log::set_max_level(active.max_level_filter());
+            old
+        };
+        log::Log::flush(&old);
+    }
+
+    /// Gets the active logger. The lock is released before the logger is used, so a log statement
+    /// that logs again while it is written, or a slow output, cannot block a change.
+    pub(crate) fn read(&self) -> Arc<Logger> {
+        // A panic while holding the lock cannot leave the configuration half-changed in a way
+        // that matters for logging, so a poisoned lock is still used.
+        Arc::clone(&self.logger.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Arc<Logger>> {
+        self.logger.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Changes the logger and updates `log::set_max_level` while holding the lock, so concurrent
+    /// changes cannot leave a stale maximum level behind.
+    ///
+    /// The logger is copied if a log statement is still being written with it.
+    pub(crate) fn update<F: FnOnce(&mut Logger)>(&self, f: F) {
+        let mut active = self.write();
+        let logger = Arc::make_mut(&mut active);
+        f(logger);
+        log::set_max_level(logger.max_level_filter());
+    }
+}
+
+/// The installed logger of `Logger::init_with_handle`, which reads the shared configuration.
+pub(crate) struct SharedLogger {
+    pub handle: LoggerHandle,
+}
+
+impl log::Log for SharedLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.handle.read().enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.handle.read().log(record)
+    }
+
+    fn flush(&self) {
+        self.handle.read().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_max_level_works() {
+        let handle = LoggerHandle::new(Logger::new().directives("info,app=trace"));
+        handle.set_max_level(log::Level::Debug);
+        assert_eq!(handle.max_level(), log::Level::Debug);
+        assert_eq!(handle.directives(), vec![Directive::new("app", log::LevelFilter::Trace)]);
+    }
+
+    #[test]
+    fn directives_work() {
+        let handle = LoggerHandle::new(Logger::new());
+        handle.add_directive("app", log::LevelFilter::Debug);
+        assert_eq!(handle.directives(), vec![Directive::new("app", log::LevelFilter::Debug)]);
+        handle.set_directives("hyper=warn,app::db=trace");
+        assert_eq!(handle.directives().len(), 2);
+        handle.clear_directives();
+        assert!(handle.directives().is_empty());
+    }
+
+    #[test]
+    fn module_path_filters_work() {
+        let handle = LoggerHandle::new(Logger::new());
+        handle.set_module_path_filters(vec![String::from("app")]);
+        assert_eq!(handle.module_path_filters(), vec![String::from("app")]);
+    }
+
+    #[test]
+    fn changes_do_not_wait_for_log_statements() {
+        let handle = LoggerHandle::new(Logger::new());
+        // A log statement that is being written keeps its logger.
+        let writing = handle.read();
+        handle.set_max_level(log::Level::Trace);
+        handle.replace(Logger::new().level(true));
+        assert_eq!(writing.level, log::Level::Warn);
+        assert!(handle.read().include_level);
+    }
+
+    #[test]
+    fn replace_works() {
+        let handle = LoggerHandle::new(Logger::new());
+        handle.replace(Logger::new().level(true).verbosity(2));
+        assert!(handle.read().include_level);
+        assert_eq!(handle.max_level(), log::Level::Debug);
+    }
+
+    #[test]
+    fn clones_share_the_logger() {
+        let handle = LoggerHandle::new(Logger::new());
+        handle.clone().set_max_level(log::Level::Trace);
+        assert_eq!(handle.max_level(), log::Level::Trace);
+    }
+}
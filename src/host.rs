@@ -0,0 +1,46 @@
+//! Resolving the name of the host.
+
+use std::sync::OnceLock;
+
+/// Gets the name of the host, or `unknown` if it cannot be determined.
+#[cfg(unix)]
+pub(crate) fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    // The name is truncated, and then possibly not terminated, if it is longer than the buffer.
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len() - 1) };
+    if result != 0 {
+        return String::from(HOSTNAME_UNKNOWN);
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    match String::from_utf8_lossy(&buf[..len]) {
+        ref name if name.is_empty() => String::from(HOSTNAME_UNKNOWN),
+        name => name.into_owned(),
+    }
+}
+
+/// Gets the name of the host, or `unknown` if it cannot be determined.
+#[cfg(not(unix))]
+pub(crate) fn hostname() -> String {
+    ::std::env::var("COMPUTERNAME").unwrap_or_else(|_| String::from(HOSTNAME_UNKNOWN))
+}
+
+const HOSTNAME_UNKNOWN: &str = "unknown";
+
+/// Gets the name of the host, which is only resolved the first time.
+pub(crate) fn cached_hostname() -> &'static str {
+    static HOSTNAME: OnceLock<String> = OnceLock::new();
+    HOSTNAME.get_or_init(hostname)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hostname_works() {
+        let name = hostname();
+        assert!(!name.is_empty());
+        assert!(!name.contains('\0'));
+        assert_eq!(cached_hostname(), name);
+    }
+}
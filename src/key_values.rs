@@ -0,0 +1,241 @@
+//! Rendering the key-value pairs of a log statement, e.g. `info!(user = 42; "login")`.
+
+use std::fmt::Write;
+
+use log;
+use log::kv::{self, VisitSource, VisitValue};
+
+use color::ColorDepth;
+use format;
+use style::Style;
+
+/// How the key-value pairs of log statements are written.
+///
+/// The pairs are written after the message in the text format, e.g. `login user=42`, as a
+/// `fields` object in the JSON format, as additional pairs in the logfmt format, and in place of
+/// the `{kv}` field of a template. By default, all pairs are written in the order of the log
+/// statement, without colors.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use loggerv::{Color, KeyValues, Style};
+///
+/// fn main() {
+///     loggerv::Logger::new()
+///         .key_values(KeyValues::new()
+///             .hide("password")
+///             .order(vec!["request_id"])
+///             .key_style(Style::new().dimmed())
+///             .value_style("user", Color::Cyan))
+///         .init()
+///         .unwrap();
+///
+///     error!(user = "alice", password = "hunter2", request_id = 7; "Login failed");
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyValues {
+    hidden: Vec<String>,
+    order: Vec<String>,
+    key_style: Style,
+    value_styles: Vec<(String, Style)>,
+}
+
+impl KeyValues {
+    /// Creates the configuration that writes all pairs in their order, without colors.
+    pub fn new() -> KeyValues {
+        KeyValues::default()
+    }
+
+    /// Hides the pairs with the key, e.g. of secrets or of values that are too long.
+    pub fn hide<S: Into<String>>(mut self, key: S) -> Self {
+        self.hidden.push(key.into());
+        self
+    }
+
+    /// Sets the keys that are written first, in this order. The pairs with other keys follow in
+    /// the order of the log statement.
+    pub fn order<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.order = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the style of all keys, if colors are enabled.
+    pub fn key_style<S: Into<Style>>(mut self, s: S) -> Self {
+        self.key_style = s.into();
+        self
+    }
+
+    /// Sets the style of the values with the key, if colors are enabled.
+    pub fn value_style<K: Into<String>, S: Into<Style>>(mut self, key: K, s: S) -> Self {
+        let key = key.into();
+        self.value_styles.retain(|v| v.0 != key);
+        self.value_styles.push((key, s.into()));
+        self
+    }
+
+    /// Collects the visible pairs of the log statement in the configured order.
+    pub(crate) fn collect(&self, record: &log::Record) -> Vec<Pair> {
+        let mut pairs = pairs(record);
+        pairs.retain(|p| !self.hidden.contains(&p.key));
+        if !self.order.is_empty() {
+            // The sort is stable, so the other pairs keep their order.
+            pairs.sort_by_key(|p| self.order.iter().position(|k| *k == p.key).unwrap_or(self.order.len()));
+        }
+        pairs
+    }
+
+    /// Writes the pairs like logfmt, each preceded by a space, and colorized if enabled.
+    pub(crate) fn write_text(&self, s: &mut String, pairs: &[Pair], colorize: bool, depth: ColorDepth) {
+        for pair in pairs {
+            s.push(' ');
+            if colorize && !self.key_style.is_plain() {
+                write!(s, "{}", self.key_style.to_ansi(depth).paint(pair.key.as_str())).expect("Writing to a string");
+            } else {
+                s.push_str(&pair.key);
+            }
+            s.push('=');
+            let style = self.value_styles.iter().find(|v| v.0 == pair.key).map(|v| v.1);
+            match style {
+                Some(style) if colorize => {
+                    let mut value = String::new();
+                    format::logfmt_value(&mut value, &pair.value);
+                    write!(s, "{}", style.to_ansi(depth).paint(value)).expect("Writing to a string");
+                },
+                _ => format::logfmt_value(s, &pair.value),
+            }
+        }
+    }
+}
+
+/// A key-value pair of a log statement, with the value rendered as text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Pair {
+    pub key: String,
+    pub value: String,
+    /// If the value is a number, a boolean, or null, which are not quoted in JSON.
+    pub primitive: bool,
+}
+
+/// Collects all pairs of the log statement in its order.
+pub(crate) fn pairs(record: &log::Record) -> Vec<Pair> {
+    let mut collector = Collector(Vec::new());
+    // Collecting never fails, since the visitors never return an error.
+    let _ = record.key_values().visit(&mut collector);
+    collector.0
+}
+
+struct Collector(Vec<Pair>);
+
+impl<'kvs> VisitSource<'kvs> for Collector {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let mut v = ValueText(None);
+        let _ = value.visit(&mut v);
+        let (value, primitive) = v.0.unwrap_or_else(|| (value.to_string(), false));
+        self.0.push(Pair {
+            key: String::from(key.as_str()),
+            value,
+            primitive,
+        });
+        Ok(())
+    }
+}
+
+/// Renders a value and determines if it is a primitive.
+struct ValueText(Option<(String, bool)>);
+
+impl<'v> VisitValue<'v> for ValueText {
+    fn visit_any(&mut self, value: kv::Value) -> Result<(), kv::Error> {
+        self.0 = Some((value.to_string(), false));
+        Ok(())
+    }
+
+    fn visit_null(&mut self) -> Result<(), kv::Error> {
+        self.0 = Some((String::from("null"), true));
+        Ok(())
+    }
+
+    fn visit_u64(&mut self, value: u64) -> Result<(), kv::Error> {
+        self.0 = Some((value.to_string(), true));
+        Ok(())
+    }
+
+    fn visit_i64(&mut self, value: i64) -> Result<(), kv::Error> {
+        self.0 = Some((value.to_string(), true));
+        Ok(())
+    }
+
+    fn visit_u128(&mut self, value: u128) -> Result<(), kv::Error> {
+        self.0 = Some((value.to_string(), true));
+        Ok(())
+    }
+
+    fn visit_i128(&mut self, value: i128) -> Result<(), kv::Error> {
+        self.0 = Some((value.to_string(), true));
+        Ok(())
+    }
+
+    fn visit_f64(&mut self, value: f64) -> Result<(), kv::Error> {
+        // JSON has no infinity or NaN, so these are written as strings.
+        self.0 = Some((value.to_string(), value.is_finite()));
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Result<(), kv::Error> {
+        self.0 = Some((value.to_string(), true));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_record<F: FnOnce(&log::Record)>(f: F) {
+        let kvs: [(&str, kv::Value); 4] = [
+            ("user", kv::Value::from("alice smith")),
+            ("attempt", kv::Value::from(3)),
+            ("password", kv::Value::from("hunter2")),
+            ("request_id", kv::Value::from(7u64)),
+        ];
+        f(&log::Record::builder()
+            .level(log::Level::Warn)
+            .args(format_args!("login failed"))
+            .key_values(&kvs)
+            .build())
+    }
+
+    #[test]
+    fn collect_works() {
+        with_record(|r| {
+            let pairs = KeyValues::new().collect(r);
+            assert_eq!(pairs.iter().map(|p| p.key.as_str()).collect::<Vec<_>>(), vec!["user", "attempt", "password", "request_id"]);
+            assert_eq!(pairs[0], Pair { key: String::from("user"), value: String::from("alice smith"), primitive: false });
+            assert_eq!(pairs[1], Pair { key: String::from("attempt"), value: String::from("3"), primitive: true });
+            let pairs = KeyValues::new().hide("password").order(vec!["request_id", "missing"]).collect(r);
+            assert_eq!(pairs.iter().map(|p| p.key.as_str()).collect::<Vec<_>>(), vec!["request_id", "user", "attempt"]);
+        });
+    }
+
+    #[test]
+    fn write_text_works() {
+        with_record(|r| {
+            let kv = KeyValues::new().hide("password").key_style(Style::new().bold()).value_style("attempt", ::Color::Red);
+            let pairs = kv.collect(r);
+            let mut s = String::new();
+            kv.write_text(&mut s, &pairs, false, ColorDepth::Ansi16);
+            assert_eq!(s, " user=\"alice smith\" attempt=3 request_id=7");
+            s.clear();
+            kv.write_text(&mut s, &pairs[1..2], true, ColorDepth::Ansi16);
+            assert_eq!(s, " \u{1b}[1mattempt\u{1b}[0m=\u{1b}[31m3\u{1b}[0m");
+        });
+    }
+}
@@ -10,8 +10,8 @@
 //!
 //! The default configuration colorizes the "tag" portion of the log statement, where the tag is
 //! the text to the left of a separator, defaulted as the colon (`:`). The message is the
-//! portion to the right of the separator and it is _not_ ever colorized. The tag includes only the
-//! module path and the separator by default.
+//! portion to the right of the separator and it is _not_ colorized unless a message style is set.
+//! The tag includes only the module path and the separator by default.
 //!
 //! ## Example
 //!
@@ -124,17 +124,80 @@ extern crate log;
 
 extern crate atty;
 extern crate ansi_term;
+extern crate chrono;
+#[cfg(unix)]
+extern crate libc;
+#[cfg(feature = "config")]
+extern crate serde;
+#[cfg(unix)]
+extern crate signal_hook;
+#[cfg(feature = "config")]
+extern crate toml;
 
 use log::{SetLoggerError};
+use std::cell::RefCell;
+use std::env;
+use std::fmt::{self, Write as FmtWrite};
 use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
 use ansi_term::Colour;
 
+mod background;
+mod capture;
+mod color;
+#[cfg(feature = "config")]
+mod config;
+mod file_path;
+mod filter;
+mod format;
+mod handle;
+mod host;
+mod key_values;
+mod reload;
+mod rotate;
+mod spec;
+mod style;
+mod template;
+#[cfg(test)]
+mod test_util;
+mod theme;
+mod timestamp;
+mod write_error;
+
+pub use background::{Background, Overflow};
+pub use capture::{start_capture, Capture, CapturedRecord};
+#[doc(hidden)]
+pub use capture::{assert_logged, MessageMatch};
+pub use color::{Color, ColorChoice, ColorDepth, ParseColorError};
+#[cfg(feature = "config")]
+pub use config::{BackgroundConfig, ConfigError, FileConfig, KeyValuesConfig, LevelConfig, LoggerConfig, OutputConfig, TimestampConfig};
+pub use file_path::FilePath;
+pub use filter::{Directive, ParseDirectiveError};
+pub use format::Format;
+pub use handle::LoggerHandle;
+pub use key_values::KeyValues;
+pub use reload::{ReloadError, ReloadThread, Reloader};
+pub use rotate::RotatingFile;
+pub use style::{ParseStyleError, Style};
+pub use theme::Theme;
+pub use timestamp::{Clock, Elapsed, Precision, Timestamp, TimestampFormat};
+pub use write_error::OnWriteError;
+
+use background::Worker;
+use handle::SharedLogger;
+use template::Template;
+use timestamp::Stopwatch;
+use write_error::WriteErrors;
+
 pub const DEFAULT_COLORS: bool = true;
 pub const DEFAULT_DEBUG_COLOR: Colour = Colour::Fixed(7); // light grey
 pub const DEFAULT_ERROR_COLOR: Colour = Colour::Fixed(9); // bright red
 pub const DEFAULT_INCLUDE_LEVEL: bool = false;
 pub const DEFAULT_INCLUDE_LINE_NUMBERS: bool = false;
+pub const DEFAULT_INCLUDE_HOSTNAME: bool = false;
 pub const DEFAULT_INCLUDE_MODULE_PATH: bool = true;
+pub const DEFAULT_INCLUDE_PID: bool = false;
+pub const DEFAULT_INCLUDE_THREAD: bool = false;
 pub const DEFAULT_INFO_COLOR: Colour = Colour::Fixed(10); // bright green
 pub const DEFAULT_LEVEL: log::Level = log::Level::Warn;
 pub const DEFAULT_OFFSET: u64 = 1;
@@ -143,28 +206,132 @@ pub const DEFAULT_TRACE_COLOR: Colour = Colour::Fixed(8); // grey
 pub const DEFAULT_WARN_COLOR: Colour = Colour::Fixed(11); // bright yellow
 pub const MODULE_PATH_UNKNOWN: &str = "unknown";
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The destination of the log statements for a level.
+///
+/// Besides the two standard streams, any `Write + Send` target can be used, such as a file, a
+/// socket, or an in-memory buffer. A `RotatingFile` can be used to write to a file that is rotated
+/// by size or by day. Log statements written to a `Writer` are only colorized with
+/// `ColorChoice::Always`.
+#[derive(Clone)]
 pub enum Output {
     Stderr,
     Stdout,
+    Writer(Arc<Mutex<dyn Write + Send>>),
+}
+
+impl Output {
+    /// Creates an output that writes to the given target.
+    ///
+    /// The target is shared, so the same output can be used for several levels and each log
+    /// statement is written as a whole while holding the lock.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use std::fs::File;
+    /// use log::Level;
+    /// use loggerv::Output;
+    ///
+    /// fn main() {
+    ///     let file = File::create(std::env::temp_dir().join("loggerv-example.log")).unwrap();
+    ///     let output = Output::writer(file);
+    ///     loggerv::Logger::new()
+    ///         .output(&Level::Warn, output.clone())
+    ///         .output(&Level::Info, output)
+    ///         .verbosity(1)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed to stderr");
+    ///     warn!("This is written to the file");
+    ///     info!("This is written to the file, too");
+    /// }
+    /// ```
+    pub fn writer<W: Write + Send + 'static>(w: W) -> Output {
+        Output::Writer(Arc::new(Mutex::new(w)))
+    }
+
+    /// Writes a whole log statement.
+    fn write_all(&self, buf: &[u8]) -> io::Result<()> {
+        match *self {
+            Output::Stderr => io::stderr().write_all(buf),
+            Output::Stdout => io::stdout().write_all(buf),
+            Output::Writer(ref w) => {
+                // The whole line was formatted before taking the lock and is written at once, so
+                // the writer is held as briefly as possible and a writer that rotates never splits
+                // a line across files.
+                let mut w = w.lock().unwrap_or_else(|e| e.into_inner());
+                w.write_all(buf)
+            },
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Output::Stderr => f.write_str("Stderr"),
+            Output::Stdout => f.write_str("Stdout"),
+            Output::Writer(_) => f.write_str("Writer(..)"),
+        }
+    }
+}
+
+impl PartialEq for Output {
+    fn eq(&self, other: &Output) -> bool {
+        match (self, other) {
+            (Output::Stderr, Output::Stderr) => true,
+            (Output::Stdout, Output::Stdout) => true,
+            (Output::Writer(a), Output::Writer(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Output {}
+
+#[derive(Debug, Clone, PartialEq)]
 struct Level {
     output: Output,
-    color: Colour,
+    style: Style,
+    message_style: Style,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Logger {
-    colors: bool,
+    background: Option<Background>,
+    color_choice: ColorChoice,
+    color_depth: ColorDepth,
+    compact_location: bool,
+    stderr_colors: bool,
+    stdout_colors: bool,
+    writer_colors: bool,
+    directives: Vec<Directive>,
+    elapsed: Option<Elapsed>,
+    env: Option<String>,
+    file_path: Option<FilePath>,
+    format: Format,
+    hostname: Option<String>,
+    include_hostname: bool,
     include_level: bool,
     include_line_numbers: bool,
     include_module_path: bool,
+    include_pid: bool,
+    include_thread: bool,
+    key_values: KeyValues,
     level: log::Level,
     offset: u64,
     separator: String,
+    stopwatch: Stopwatch,
+    template: Option<Template>,
+    thread_width: usize,
+    timestamp: Option<Timestamp>,
     verbosity: Option<u64>,
+    worker: Option<Arc<Worker>>,
+    write_errors: WriteErrors,
     error: Level,
     warn: Level,
     info: Level,
@@ -176,9 +343,10 @@ pub struct Logger {
 impl Logger {
     /// Creates a new instance of the verbosity-based logger.
     ///
-    /// The default level is WARN. Color is enabled if the parent application or library is running
-    /// from a terminal, i.e. running a tty. The default separator is the ": " string. The default
-    /// output format is `module path: message`. The following default colors are used:
+    /// The default level is WARN. Color is enabled for `stdout` and `stderr` separately if the
+    /// stream is a terminal, i.e. a tty, unless disabled by the `NO_COLOR` or `CLICOLOR`
+    /// environment variables; see `ColorChoice`. The default separator is the ": " string. The
+    /// default output format is `module path: message`. The following default colors are used:
     ///
     /// | Level | Color         |
     /// |-------|---------------|
@@ -189,78 +357,777 @@ impl Logger {
     /// | Trace | Grey          |
     pub fn new() -> Logger {
         Logger {
-            colors: DEFAULT_COLORS && atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr),
+            background: None,
+            color_choice: ColorChoice::Auto,
+            color_depth: ColorDepth::detect(),
+            compact_location: false,
+            stderr_colors: DEFAULT_COLORS && ColorChoice::Auto.resolve(atty::is(atty::Stream::Stderr)),
+            stdout_colors: DEFAULT_COLORS && ColorChoice::Auto.resolve(atty::is(atty::Stream::Stdout)),
+            writer_colors: DEFAULT_COLORS && ColorChoice::Auto.resolve(false),
+            directives: Vec::new(),
+            elapsed: None,
+            env: None,
+            file_path: None,
+            format: Format::Text,
+            hostname: None,
+            include_hostname: DEFAULT_INCLUDE_HOSTNAME,
             include_level: DEFAULT_INCLUDE_LEVEL,
             include_line_numbers: DEFAULT_INCLUDE_LINE_NUMBERS,
             include_module_path: DEFAULT_INCLUDE_MODULE_PATH,
+            include_pid: DEFAULT_INCLUDE_PID,
+            include_thread: DEFAULT_INCLUDE_THREAD,
+            key_values: KeyValues::new(),
             level: DEFAULT_LEVEL,
             offset: DEFAULT_OFFSET,
             separator: String::from(DEFAULT_SEPARATOR),
+            stopwatch: Stopwatch::new(),
+            template: None,
+            thread_width: 0,
+            timestamp: None,
             verbosity: None,
+            worker: None,
+            write_errors: WriteErrors::new(OnWriteError::Panic),
             error: Level {
                 output: Output::Stderr,
-                color: DEFAULT_ERROR_COLOR,
+                style: Style::from(DEFAULT_ERROR_COLOR),
+                message_style: Style::new(),
             },
             warn: Level {
                 output: Output::Stderr,
-                color: DEFAULT_WARN_COLOR,
+                style: Style::from(DEFAULT_WARN_COLOR),
+                message_style: Style::new(),
             },
             info: Level {
                 output: Output::Stderr,
-                color: DEFAULT_INFO_COLOR,
+                style: Style::from(DEFAULT_INFO_COLOR),
+                message_style: Style::new(),
             },
             debug: Level {
                 output: Output::Stderr,
-                color: DEFAULT_DEBUG_COLOR,
+                style: Style::from(DEFAULT_DEBUG_COLOR),
+                message_style: Style::new(),
             },
             trace: Level {
                 output: Output::Stderr,
-                color: DEFAULT_TRACE_COLOR,
+                style: Style::from(DEFAULT_TRACE_COLOR),
+                message_style: Style::new(),
             },
             module_path_filters: Vec::new(),
         }
     }
 
-    /// Sets the color for a level.
+    /// Creates a new instance of the logger that is also configured by the environment variable.
+    ///
+    /// This is a shorthand for `Logger::new().env(name)`. See the `env` method for the syntax of
+    /// the variable.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::from_env("MYAPP_LOG")
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed, unless MYAPP_LOG=off");
+    /// }
+    /// ```
+    pub fn from_env(name: &str) -> Logger {
+        Logger::new().env(name)
+    }
+
+    /// Configures the logger from the environment variable when the logger is initialized.
+    ///
+    /// The variable is a comma-separated list of entries:
+    ///
+    /// | Entry                              | Effect                                          |
+    /// |------------------------------------|-------------------------------------------------|
+    /// | `debug`                            | Sets the level of all modules without a directive |
+    /// | `myapp::db=trace`                  | Adds a directive, see the `directive` method    |
+    /// | `color=auto`, `always`, or `never` | Colorizes based on the terminal, always, or never |
+    /// | `format=text`, `json`, or `logfmt` | Sets the format                                 |
+    /// | `output.info=stderr` or `stdout`   | Sets the output of a level                      |
+    /// | `color.warn=#ff8800`               | Sets the color of a level, see `Color`          |
+    /// | `theme=solarized`                  | Sets the styles of all levels, see `Theme`      |
+    /// | `+level` or `-level`               | Includes or excludes the level                  |
+    /// | `+line_numbers` or `-line_numbers` | Includes or excludes the line numbers           |
+    /// | `+module_path` or `-module_path`   | Includes or excludes the module path            |
+    /// | `+timestamp` or `-timestamp`       | Includes or excludes the timestamp              |
+    ///
+    /// For example, `MYAPP_LOG=info,hyper=warn,color=never,+timestamp`. Invalid entries are
+    /// ignored. If the variable is not set, the logger is unchanged.
+    ///
+    /// The variable takes precedence over the builder methods, regardless of the order in which
+    /// they are called. A level in the variable overrides the verbosity and the `max_level`
+    /// method, while directives in the variable are added to the ones from the builder methods
+    /// and win for the same module. If the variable only contains directives, the verbosity still
+    /// decides the level of the other modules.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     std::env::set_var("MYAPP_LOG", "info,+level");
+    ///     loggerv::Logger::new()
+    ///         .verbosity(0)
+    ///         .env("MYAPP_LOG")
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     info!("This is printed with the level, despite the verbosity of zero");
+    /// }
+    /// ```
+    pub fn env(mut self, name: &str) -> Self {
+        self.env = Some(String::from(name));
+        self
+    }
+
+    /// Creates a new instance of the logger from a configuration, e.g. from a config file.
+    ///
+    /// See `LoggerConfig` for an example. This requires the `config` feature, which is enabled by
+    /// default.
+    #[cfg(feature = "config")]
+    pub fn from_config(config: &LoggerConfig) -> Result<Logger, ConfigError> {
+        config.apply(Logger::new())
+    }
+
+    /// Sets the color for a level, i.e. the foreground color of the tag's style.
+    ///
+    /// The color is either a `Color`, e.g. parsed from a string, or an `ansi_term::Colour`. A
+    /// 24-bit color is downgraded to the closest one the terminal supports; see `color_depth`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use log::Level;
+    /// use loggerv::Color;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .color(&Level::Error, Color::Fixed(7))
+    ///         .color(&Level::Warn, "#ff8800".parse::<Color>().unwrap())
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed in light grey instead of bright red");
+    ///     warn!("This is printed in orange instead of bright yellow");
+    /// }
+    /// ```
+    pub fn color<C: Into<Color>>(mut self, l: &log::Level, c: C) -> Self {
+        {
+            let level = self.select_level_mut(l);
+            level.style = level.style.fg(c);
+        }
+        self
+    }
+
+    /// Sets the style of the tag for a level, i.e. the colors and attributes like bold.
+    ///
+    /// The default is only the foreground color of the level. See `Style` for the syntax to parse
+    /// a style from a string.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use log::Level;
+    /// use loggerv::{Color, Style};
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .style(&Level::Error, Style::new().fg(Color::White).on(Color::Red).bold())
+    ///         .style(&Level::Warn, "underline bright-yellow".parse::<Style>().unwrap())
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with a bold white tag on red");
+    /// }
+    /// ```
+    pub fn style<S: Into<Style>>(mut self, l: &log::Level, s: S) -> Self {
+        self.select_level_mut(l).style = s.into();
+        self
+    }
+
+    /// Sets the style of the message for a level, including the separator.
+    ///
+    /// The default is the plain style, i.e. the message is not colorized. The message is only
+    /// colorized together with the tag; see `color_choice`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use log::Level;
+    /// use loggerv::{Color, Style};
+    ///
+    /// fn main() {
+    ///     let bold_red = Style::new().fg(Color::Red).bold();
+    ///     loggerv::Logger::new()
+    ///         .style(&Level::Error, bold_red)
+    ///         .message_style(&Level::Error, bold_red)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This whole line is printed in bold red");
+    /// }
+    /// ```
+    pub fn message_style<S: Into<Style>>(mut self, l: &log::Level, s: S) -> Self {
+        self.select_level_mut(l).message_style = s.into();
+        self
+    }
+
+    /// Sets the styles of the tag and the message for all levels from a theme.
+    ///
+    /// See `Theme` for the built-in themes and registering custom ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::Theme;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .theme(&Theme::named("light-background").unwrap())
+    ///         .verbosity(2)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     debug!("This is printed in dark blue instead of light grey");
+    /// }
+    /// ```
+    pub fn theme(mut self, t: &Theme) -> Self {
+        for l in &[log::Level::Error, log::Level::Warn, log::Level::Info, log::Level::Debug, log::Level::Trace] {
+            let level = self.select_level_mut(l);
+            level.style = t.style_for(l);
+            level.message_style = t.message_style_for(l);
+        }
+        self
+    }
+
+    /// Sets the separator string.
+    ///
+    /// The separator is the string between the "tag" and the message that make up a log statement.
+    /// The tag will be colorized if enabled, while the message will not. The default is `: `.
+    ///
+    /// If the level, line numbers, and module path are all _not_ included in the log statement,
+    /// then the separator is changed to the empty string to avoid printing a lone string or
+    /// character before each message portion of the log statement.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .separator(" = ")
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with an equal sign between the module path and this message");
+    /// }
+    /// ```
+    pub fn separator(mut self, s: &str) -> Self {
+        self.separator = String::from(s);
+        self
+    }
+
+    /// Sets the encoding of the log statements.
+    ///
+    /// The default is `Format::Text`, the colorized tag followed by the separator and the message.
+    /// With `Format::Json`, each log statement is written as one JSON object per line, and with
+    /// `Format::Logfmt` as one line of `key=value` pairs. Both bypass the separator and colors
+    /// entirely. With `Format::Template`, the log statement follows a user-defined layout; see
+    /// the `template` method.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::{Format, Timestamp};
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .format(Format::Json)
+    ///         .timestamp(Timestamp::rfc3339())
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed as a JSON object with a timestamp field");
+    /// }
+    /// ```
+    pub fn format(mut self, f: Format) -> Self {
+        self.format = f;
+        self
+    }
+
+    /// Sets a user-defined layout of the log statements.
+    ///
+    /// This is a shorthand for `format(Format::Template(...))`. The template is literal text with
+    /// fields in braces, which replaces the tag and the separator. The fields are:
+    ///
+    /// | Field        | Value                                                           |
+    /// |--------------|-----------------------------------------------------------------|
+    /// | `{time}`     | The timestamp, or a UTC RFC 3339 timestamp if none is set       |
+    /// | `{elapsed}`  | The elapsed time, or the time since initialization if none is set |
+    /// | `{level}`    | The level, e.g. `WARN`                                          |
+    /// | `{hostname}` | The name of the host                                            |
+    /// | `{pid}`      | The id of the process                                           |
+    /// | `{thread}`   | The name of the thread, or its id if it has no name             |
+    /// | `{target}`   | The target                                                      |
+    /// | `{module}`   | The module path                                                 |
+    /// | `{file}`     | The source file                                                 |
+    /// | `{line}`     | The line number                                                 |
+    /// | `{msg}`      | The message                                                     |
+    /// | `{kv}`       | The key-value pairs, e.g. `user=42 admin=false`                 |
+    ///
+    /// A field may be followed by a colon and a padding specifier like in the `format!` macro,
+    /// i.e. an optional fill character, an optional alignment (`<`, `>`, or `^`), and a minimum
+    /// width, e.g. `{level:>5}`. Braces are written as `{{` and `}}`, and an unknown field is
+    /// written as is. All fields except the message and the key-value pairs are colorized like the
+    /// tag if enabled, and the pairs are colorized like in the text format. The template is parsed
+    /// once, when the logger is initialized.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .template("{time} {level:>5} {module}:{line} | {msg}")
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed after the time, the padded level, the module, and the line");
+    /// }
+    /// ```
+    pub fn template(self, t: &str) -> Self {
+        self.format(Format::Template(String::from(t)))
+    }
+
+    /// Sets when to colorize the output.
+    ///
+    /// The default is `ColorChoice::Auto`, which colorizes the output if it is a terminal and
+    /// follows the `NO_COLOR`, `CLICOLOR`, and `CLICOLOR_FORCE` environment variables. Each level
+    /// is colorized based on its own output, so redirecting `stdout` to a file keeps the colors on
+    /// `stderr`. A writer output is only colorized with `ColorChoice::Always` or with
+    /// `CLICOLOR_FORCE`. The environment is read again when the logger is initialized, so changes
+    /// between building and initializing the logger are not lost.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::ColorChoice;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .color_choice(ColorChoice::Always)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with colors, even when piped to `less -R`");
+    /// }
+    /// ```
+    pub fn color_choice(mut self, c: ColorChoice) -> Self {
+        self.color_choice = c;
+        self.resolve_colors(|name| env::var(name).ok());
+        self
+    }
+
+    /// Sets the number of colors the terminal supports.
+    ///
+    /// The default is detected from the `COLORTERM` and `TERM` environment variables; see
+    /// `ColorDepth`. Colors that the terminal does not support are downgraded to the closest
+    /// supported one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::{Color, ColorDepth};
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .color(&log::Level::Error, Color::Rgb(255, 136, 0))
+    ///         .color_depth(ColorDepth::Ansi256)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed in color 208 of the 256-color palette");
+    /// }
+    /// ```
+    pub fn color_depth(mut self, d: ColorDepth) -> Self {
+        self.color_depth = d;
+        self
+    }
+
+    /// Enables or disables colorizing the output.
+    ///
+    /// If an output is _not_ a terminal, then it is _not_ colorized regardless of this value. This
+    /// is the same as `color_choice` with `ColorChoice::Auto` or
+    /// `ColorChoice::Never`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .colors(false)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed without any colorization");
+    /// }
+    /// ```
+    pub fn colors(self, c: bool) -> Self {
+        self.color_choice(if c { ColorChoice::Auto } else { ColorChoice::Never })
+    }
+
+    /// Disables colorizing the output.
+    ///
+    /// The default is to colorize `stdout` and `stderr` unless they are redirected or piped, i.e.
+    /// not a tty. This is the same as `color_choice` with `ColorChoice::Never`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .no_colors()
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed without any colorization");
+    /// }
+    /// ```
+    pub fn no_colors(self) -> Self {
+        self.color_choice(ColorChoice::Never)
+    }
+
+    /// Enables or disables including line numbers in the "tag" portion of the log statement.
+    ///
+    /// The tag is the text to the left of the separator.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .line_numbers(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the module path and the line number surrounded by
+    ///     parentheses");
+    /// }
+    /// ```
+    pub fn line_numbers(mut self, i: bool) -> Self {
+        self.include_line_numbers = i;
+        self
+    }
+
+    /// Includes the path of the source file in the "tag" portion of the log statement, trimmed
+    /// according to the mode.
+    ///
+    /// The path is written in parentheses together with the line number, if line numbers are
+    /// enabled, e.g. `(src/net.rs, line 42)`, or in the compact style, e.g. `net.rs:42`. See
+    /// `compact_location`. The path also replaces the full path in the JSON format and the `{file}`
+    /// field of a template, and is added to the logfmt format. The default is to not include the
+    /// path.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::FilePath;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .file(FilePath::CrateRelative)
+    ///         .line_numbers(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the path of the file and the line number");
+    /// }
+    /// ```
+    pub fn file(mut self, p: FilePath) -> Self {
+        self.file_path = Some(p);
+        self
+    }
+
+    /// Disables the path of the source file in the "tag" portion of the log statement.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .no_file()
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed without the path of the file");
+    /// }
+    /// ```
+    pub fn no_file(mut self) -> Self {
+        self.file_path = None;
+        self
+    }
+
+    /// Enables or disables writing the path of the source file and the line number in the
+    /// compact style, e.g. `net.rs:42` instead of `(net.rs, line 42)`, which many editors and
+    /// terminals recognize as a link. It has no effect without the path of the file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::FilePath;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .file(FilePath::Basename)
+    ///         .line_numbers(true)
+    ///         .compact_location(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the file and the line number, e.g. main.rs:14");
+    /// }
+    /// ```
+    pub fn compact_location(mut self, c: bool) -> Self {
+        self.compact_location = c;
+        self
+    }
+
+    /// Includes the elapsed time in the "tag" portion of the log statement.
+    ///
+    /// The elapsed time is measured with a monotonic clock, either since the logger was
+    /// initialized or since the previous log statement, and written with millisecond precision,
+    /// e.g. `+0.153s`. It follows the timestamp, if any. The default is to not include the elapsed
+    /// time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::Elapsed;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .elapsed(Elapsed::SinceStart)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the time since initialization, e.g. +0.000s");
+    /// }
+    /// ```
+    pub fn elapsed(mut self, e: Elapsed) -> Self {
+        self.elapsed = Some(e);
+        self
+    }
+
+    /// Disables the elapsed time in the "tag" portion of the log statement.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .no_elapsed()
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed without the elapsed time");
+    /// }
+    /// ```
+    pub fn no_elapsed(mut self) -> Self {
+        self.elapsed = None;
+        self
+    }
+
+    /// Enables or disables including the level in the log statement's tag portion. The tag of the
+    /// log statement is the text to the left of the separator.
+    ///
+    /// If the level and the module path are both inculded, then the module path is surrounded by
+    /// square brackets.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .level(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the 'ERROR' and the module path is surrounded in square
+    ///     brackets");
+    /// }
+    /// ```
+    pub fn level(mut self, i: bool) -> Self {
+        self.include_level = i;
+        self
+    }
+
+    /// Enables or disables including the name of the host in the log statement's tag portion, e.g.
+    /// to tell apart the log statements of many machines in a merged log.
+    ///
+    /// The name follows the timestamp and the elapsed time, if any. It is resolved once when the
+    /// logger is initialized. The default is to not include the name of the host.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .hostname(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the name of the host");
+    /// }
+    /// ```
+    pub fn hostname(mut self, i: bool) -> Self {
+        self.include_hostname = i;
+        self
+    }
+
+    /// Enables or disables including the id of the process in the log statement's tag portion,
+    /// e.g. to tell apart the log statements of parallel invocations in a merged log.
+    ///
+    /// The id is written in square brackets, after the name of the host if that is included, e.g.
+    /// `build-7[4242]`. The default is to not include the id of the process.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .pid(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with the id of the process in square brackets");
+    /// }
+    /// ```
+    pub fn pid(mut self, i: bool) -> Self {
+        self.include_pid = i;
+        self
+    }
+
+    /// Enables or disables including the name of the current thread in the log statement's tag
+    /// portion, e.g. to tell the threads of a worker pool apart.
+    ///
+    /// The name follows the timestamp, the elapsed time, the host, and the process, if any. A
+    /// thread without a name, i.e. one that was not started with `std::thread::Builder::name`, is
+    /// written as its id, e.g. `ThreadId(5)`. The default is to not include the thread.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .thread(true)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     error!("This is printed with 'main' before the module path");
+    /// }
+    /// ```
+    pub fn thread(mut self, i: bool) -> Self {
+        self.include_thread = i;
+        self
+    }
+
+    /// Pads the thread name in the tag with spaces to a minimum width, so the columns after it
+    /// align. Longer names are not truncated. The default is zero, i.e. no padding.
     ///
     /// # Example
     ///
-    /// ```
+    /// ```rust
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
-    /// extern crate ansi_term;
-    ///
-    /// use log::Level;
-    /// use ansi_term::Colour;
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .color(&Level::Error, Colour::Fixed(7))
+    ///         .thread(true)
+    ///         .thread_width(12)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed in light grey instead of bright red");
+    ///     error!("This is printed with 'main' padded to 12 characters");
     /// }
     /// ```
-    pub fn color(mut self, l: &log::Level, c: Colour) -> Self {
-        match *l {
-            log::Level::Error => self.error.color = c,
-            log::Level::Warn => self.warn.color = c,
-            log::Level::Info => self.info.color = c,
-            log::Level::Debug => self.debug.color = c,
-            log::Level::Trace => self.trace.color = c,
-        }
+    pub fn thread_width(mut self, w: usize) -> Self {
+        self.thread_width = w;
         self
     }
 
-    /// Sets the separator string.
+    /// Includes a timestamp at the start of the "tag" portion of the log statement.
     ///
-    /// The separator is the string between the "tag" and the message that make up a log statement.
-    /// The tag will be colorized if enabled, while the message will not. The default is `: `.
-    ///
-    /// If the level, line numbers, and module path are all _not_ included in the log statement,
-    /// then the separator is changed to the empty string to avoid printing a lone string or
-    /// character before each message portion of the log statement.
+    /// The timestamp is colorized together with the rest of the tag. The default is to not
+    /// include a timestamp.
     ///
     /// # Example
     ///
@@ -268,24 +1135,23 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::{Precision, Timestamp};
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .separator(" = ")
+    ///         .timestamp(Timestamp::rfc3339().precision(Precision::Millis))
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed with an equal sign between the module path and this message");
+    ///     error!("This is printed with the UTC date and time, e.g. 2018-03-01T14:05:09.123Z");
     /// }
     /// ```
-    pub fn separator(mut self, s: &str) -> Self {
-        self.separator = String::from(s);
+    pub fn timestamp(mut self, t: Timestamp) -> Self {
+        self.timestamp = Some(t);
         self
     }
 
-    /// Enables or disables colorizing the output.
-    ///
-    /// If the logger is _not_ used in a terminal, then the output is _not_ colorized regardless of
-    /// this value.
+    /// Disables the timestamp in the "tag" portion of the log statement.
     ///
     /// # Example
     ///
@@ -295,22 +1161,23 @@ impl Logger {
     ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .colors(false)
+    ///         .no_timestamp()
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed without any colorization");
+    ///     error!("This is printed without a timestamp");
     /// }
     /// ```
-    pub fn colors(mut self, c: bool) -> Self {
-        self.colors = c && atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr);
+    pub fn no_timestamp(mut self) -> Self {
+        self.timestamp = None;
         self
     }
 
-    /// Disables colorizing the output.
+    /// Writes the log statements on a background thread instead of the calling thread.
     ///
-    /// The default is to colorize the output unless `stdout` and `stderr` are redirected or piped,
-    /// i.e. not a tty.
+    /// The default is to write on the calling thread. See `Background` for how the log statements
+    /// are queued and when the queue is drained. If the thread cannot be started, the log
+    /// statements are written on the calling thread.
     ///
     /// # Example
     ///
@@ -318,23 +1185,34 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::{Background, Overflow};
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .no_colors()
+    ///         .background(Background::new().overflow(Overflow::DropNewest))
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed without any colorization");
+    ///     error!("This is written on a background thread, or dropped if the queue is full");
+    ///     log::logger().flush();
     /// }
     /// ```
-    pub fn no_colors(mut self) -> Self {
-        self. colors = false;
+    pub fn background(mut self, b: Background) -> Self {
+        self.background = Some(b);
         self
     }
 
-    /// Enables or disables including line numbers in the "tag" portion of the log statement.
+    /// Writes the log statements on the calling thread, which is the default.
+    pub fn no_background(mut self) -> Self {
+        self.background = None;
+        self
+    }
+
+    /// Sets how the key-value pairs of log statements are written, e.g. of `info!(user = 42;
+    /// "login")`.
     ///
-    /// The tag is the text to the left of the separator.
+    /// The default is to write all pairs after the message, e.g. `login user=42`, in the order of
+    /// the log statement. See `KeyValues` for hiding, ordering, and styling the pairs.
     ///
     /// # Example
     ///
@@ -342,26 +1220,26 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::KeyValues;
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .line_numbers(true)
+    ///         .key_values(KeyValues::new().hide("token"))
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed with the module path and the line number surrounded by
-    ///     parentheses");
+    ///     error!(user = 42, token = "secret"; "This is printed with the user but not the token");
     /// }
     /// ```
-    pub fn line_numbers(mut self, i: bool) -> Self {
-        self.include_line_numbers = i;
+    pub fn key_values(mut self, kv: KeyValues) -> Self {
+        self.key_values = kv;
         self
     }
 
-    /// Enables or disables including the level in the log statement's tag portion. The tag of the
-    /// log statement is the text to the left of the separator.
+    /// Sets what to do when a log statement cannot be written to its output.
     ///
-    /// If the level and the module path are both inculded, then the module path is surrounded by
-    /// square brackets.
+    /// The default is `OnWriteError::Panic`, which ignores broken pipes, so piping the output into
+    /// `head` ends quietly. See `OnWriteError` for the other policies.
     ///
     /// # Example
     ///
@@ -369,18 +1247,19 @@ impl Logger {
     /// #[macro_use] extern crate log;
     /// extern crate loggerv;
     ///
+    /// use loggerv::OnWriteError;
+    ///
     /// fn main() {
     ///     loggerv::Logger::new()
-    ///         .level(true)
+    ///         .on_write_error(OnWriteError::Silence)
     ///         .init()
     ///         .unwrap();
     ///
-    ///     error!("This is printed with the 'ERROR' and the module path is surrounded in square
-    ///     brackets");
+    ///     error!("This is printed until the first error");
     /// }
     /// ```
-    pub fn level(mut self, i: bool) -> Self {
-        self.include_level = i;
+    pub fn on_write_error(mut self, p: OnWriteError) -> Self {
+        self.write_errors = WriteErrors::new(p);
         self
     }
 
@@ -525,6 +1404,65 @@ impl Logger {
         self
     }
 
+    /// Sets the maximum level for the log statements of a module and its submodules.
+    ///
+    /// The directive with the longest matching module decides the level of a log statement, and
+    /// the level set by the verbosity or the `max_level` method is used for modules without a
+    /// matching directive. The module is matched against the target of a log statement, which is
+    /// the module path unless set explicitly in the logging macro. An empty module applies to all
+    /// modules.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use log::LevelFilter;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .directive(module_path!(), LevelFilter::Debug)
+    ///         .directive("hyper", LevelFilter::Error)
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     debug!("This is printed");
+    /// }
+    /// ```
+    pub fn directive<S: Into<String>>(mut self, module: S, level: log::LevelFilter) -> Self {
+        self.directives.push(Directive::new(module, level));
+        self
+    }
+
+    /// Adds the directives from a comma-separated list.
+    ///
+    /// A list looks like `myapp=debug,hyper=warn,myapp::db=trace`.
+    /// Each entry is either `module=level` or a bare level, which applies to all modules without a
+    /// more specific directive and so replaces the level set by the verbosity or the `max_level`
+    /// method. Invalid entries are ignored; parse a `Directive` to detect them. See the
+    /// `directive` method for how the level of a log statement is decided.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     loggerv::Logger::new()
+    ///         .directives("info,hyper=warn")
+    ///         .init()
+    ///         .unwrap();
+    ///
+    ///     info!("This is printed");
+    /// }
+    /// ```
+    pub fn directives(mut self, spec: &str) -> Self {
+        self.directives.extend(filter::parse_directives(spec));
+        self
+    }
+
     /// Sets the base level.
     ///
     /// The base level is the level used with zero (0) verbosity. The default is WARN. So, ERROR
@@ -584,8 +1522,9 @@ impl Logger {
 
     /// Sets the output for a level.
     ///
-    /// The output is either `stderr` or `stdout`. The default is for ERROR and WARN to be written
-    /// to `stderr` and INFO, DEBUG, and TRACE to `stdout`.
+    /// The output is either `stderr`, `stdout`, or any writer created with `Output::writer`. The
+    /// default is for ERROR and WARN to be written to `stderr` and INFO, DEBUG, and TRACE to
+    /// `stdout`.
     ///
     /// # Example
     ///
@@ -681,9 +1620,9 @@ impl Logger {
     ///
     /// # Example
     ///
-    /// If the tag will be empty because the level, line numbers, and module path were all
-    /// disabled, then the separator is changed to the empty string to avoid writing a long
-    /// character in front of each message for each log statement.
+    /// If the tag will be empty because all of its parts, e.g. the level, line numbers, and module
+    /// path, were disabled, then the separator is changed to the empty string to avoid writing a
+    /// long character in front of each message for each log statement.
     ///
     ///
     /// ```rust
@@ -705,7 +1644,55 @@ impl Logger {
     ///     trace!("This is not printed to stdout");
     /// }
     /// ```
-    pub fn init(mut self) -> Result<(), SetLoggerError> {
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let logger = self.prepare();
+        log::set_max_level(logger.max_level_filter());
+        log::set_boxed_logger(Box::new(logger))?;
+        capture::installed();
+        Ok(())
+    }
+
+    /// Initializes the logger and returns a handle to change it later.
+    ///
+    /// This also consumes the logger, but the level, the directives, and the module path filters
+    /// can be changed through the handle, e.g. to toggle debug output with a key stroke or a
+    /// signal. See `LoggerHandle` for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// fn main() {
+    ///     let handle = loggerv::Logger::new()
+    ///         .init_with_handle()
+    ///         .unwrap();
+    ///
+    ///     info!("This is not printed");
+    ///     handle.add_directive(module_path!(), log::LevelFilter::Info);
+    ///     info!("This is printed");
+    /// }
+    /// ```
+    pub fn init_with_handle(self) -> Result<LoggerHandle, SetLoggerError> {
+        let logger = self.prepare();
+        let max_level = logger.max_level_filter();
+        let handle = LoggerHandle::new(logger);
+        log::set_boxed_logger(Box::new(SharedLogger { handle: handle.clone() }))?;
+        capture::installed();
+        log::set_max_level(max_level);
+        Ok(handle)
+    }
+
+    /// Finishes the configuration before the logger is installed.
+    fn prepare(mut self) -> Logger {
+        // The environment variable is applied first, so its settings are treated like any other
+        // builder setting below.
+        if let Some(name) = self.env.take() {
+            if let Ok(s) = env::var(&name) {
+                self = spec::apply(self, &s);
+            }
+        }
         // If there is no level, line number, or module path in the tag, then the tag will always
         // be empty. The separator should also be empty so only the message component is printed
         // for the log statement; otherwise, there is a weird floating colon in front of every log
@@ -714,7 +1701,7 @@ impl Logger {
         // It is better to do it here than in the `log` function because it only has to be
         // determined once at initialization as opposed to every call to the `log` function. So
         // a potentially slight performance improvement.
-        if !self.include_level && !self.include_line_numbers && !self.include_module_path {
+        if !self.include_level && !self.include_line_numbers && self.file_path.is_none() && !self.include_module_path && !self.include_thread && !self.include_hostname && !self.include_pid && self.timestamp.is_none() && self.elapsed.is_none() {
             self.separator = String::new();
         }
         // The level is set based on verbosity only if the `verbosity` method has been used and
@@ -735,74 +1722,259 @@ impl Logger {
                 _ => log::Level::Trace,
             };
         }
-        log::set_max_level(self.level.to_level_filter());
-        log::set_boxed_logger(Box::new(self))
+        // The environment may have changed since the color choice was made.
+        self.resolve_colors(|name| env::var(name).ok());
+        // The elapsed time is measured from initialization, not from the creation of the logger.
+        self.stopwatch = Stopwatch::new();
+        if let Format::Template(ref t) = self.format {
+            self.template = Some(Template::parse(t));
+        }
+        // The host is not expected to be renamed while the process runs.
+        if self.include_hostname || self.template.as_ref().is_some_and(Template::has_hostname) {
+            self.hostname = Some(host::hostname());
+        }
+        // Each prepared logger has its own thread, which is stopped when the logger is replaced.
+        let errors = self.write_errors.clone();
+        self.worker = self.background.and_then(|b| Worker::spawn(&b, errors).ok()).map(Arc::new);
+        self
+    }
+
+    /// Gets the level for log statements with the target.
+    fn level_for(&self, target: &str) -> log::LevelFilter {
+        filter::level_for(&self.directives, target).unwrap_or_else(|| self.level.to_level_filter())
+    }
+
+    /// Gets the most verbose level of the level and all directives.
+    ///
+    /// This is used for `log::set_max_level`, so log statements that no directive enables are
+    /// discarded cheaply by the logging macros.
+    fn max_level_filter(&self) -> log::LevelFilter {
+        self.directives.iter()
+            .map(|d| d.level())
+            .fold(self.level.to_level_filter(), ::std::cmp::max)
+    }
+
+    /// Gets the configuration of a level.
+    fn select_level(&self, l: &log::Level) -> &Level {
+        match *l {
+            log::Level::Error => &self.error,
+            log::Level::Warn => &self.warn,
+            log::Level::Info => &self.info,
+            log::Level::Debug => &self.debug,
+            log::Level::Trace => &self.trace,
+        }
     }
 
-    /// Gets the color to use for the log statement's tag based on level.
-    fn select_color(&self, l: &log::Level) -> Colour {
+    fn select_level_mut(&mut self, l: &log::Level) -> &mut Level {
         match *l {
-            log::Level::Error => self.error.color,
-            log::Level::Warn => self.warn.color,
-            log::Level::Info => self.info.color,
-            log::Level::Debug => self.debug.color,
-            log::Level::Trace => self.trace.color,
+            log::Level::Error => &mut self.error,
+            log::Level::Warn => &mut self.warn,
+            log::Level::Info => &mut self.info,
+            log::Level::Debug => &mut self.debug,
+            log::Level::Trace => &mut self.trace,
         }
     }
 
+    /// Gets the style to use for the log statement's tag based on level.
+    fn select_style(&self, l: &log::Level) -> ansi_term::Style {
+        self.select_level(l).style.to_ansi(self.color_depth)
+    }
+
+    /// Gets the style to use for the log statement's message based on level.
+    fn select_message_style(&self, l: &log::Level) -> ansi_term::Style {
+        self.select_level(l).message_style.to_ansi(self.color_depth)
+    }
+
     /// Gets the output stream to use for the level.
-    fn select_output(&self, l: &log::Level) -> Output {
+    fn select_output(&self, l: &log::Level) -> &Output {
         match *l {
-            log::Level::Error => self.error.output,
-            log::Level::Warn => self.warn.output,
-            log::Level::Info => self.info.output,
-            log::Level::Debug => self.debug.output,
-            log::Level::Trace => self.trace.output,
+            log::Level::Error => &self.error.output,
+            log::Level::Warn => &self.warn.output,
+            log::Level::Info => &self.info.output,
+            log::Level::Debug => &self.debug.output,
+            log::Level::Trace => &self.trace.output,
+        }
+    }
+
+    /// Decides for each kind of output if it is colorized, with the environment variables from
+    /// the function.
+    fn resolve_colors<F>(&mut self, var: F)
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let c = self.color_choice;
+        self.stderr_colors = c.resolve_with(atty::is(atty::Stream::Stderr), &var);
+        self.stdout_colors = c.resolve_with(atty::is(atty::Stream::Stdout), &var);
+        self.writer_colors = c.resolve_with(false, &var);
+    }
+
+    /// Determines if the log statements of the level are colorized.
+    fn should_colorize(&self, l: &log::Level) -> bool {
+        match *self.select_output(l) {
+            Output::Stderr => self.stderr_colors,
+            Output::Stdout => self.stdout_colors,
+            Output::Writer(_) => self.writer_colors,
         }
     }
 
-    /// Creates the tag portion of the log statement based on the configuration.
+    /// Appends the tag portion of the log statement based on the configuration.
     ///
     /// The tag portion is the of the log statement is the text to the left of the separator, while
-    /// the text to the right of the separator is the message.
-    fn create_tag(&self, record: &log::Record) -> String {
+    /// the text to the right of the separator is the message. The tag is written piece by piece
+    /// instead of being assembled from intermediate strings, since this runs for every log
+    /// statement.
+    fn write_tag(&self, s: &mut String, record: &log::Record, colorize: bool) {
         let level = record.level();
-        let level_text = if self.include_level {
-            level.to_string()
-        } else {
-            String::new()
-        };
-
-        let module_path_text = if self.include_module_path {
+        let style = self.select_style(&level);
+        if colorize {
+            write!(s, "{}", style.prefix()).expect("Writing to a string");
+        }
+        let start = s.len();
+        if let Some(ref t) = self.timestamp {
+            t.write_now(s);
+        }
+        if let Some(e) = self.elapsed {
+            if s.len() > start {
+                s.push(' ');
+            }
+            Stopwatch::write(s, self.stopwatch.lap(e));
+        }
+        if self.include_hostname || self.include_pid {
+            if s.len() > start {
+                s.push(' ');
+            }
+            if self.include_hostname {
+                s.push_str(self.resolved_hostname());
+            }
+            if self.include_pid {
+                write!(s, "[{}]", ::std::process::id()).expect("Writing to a string");
+            }
+        }
+        if self.include_thread {
+            if s.len() > start {
+                s.push(' ');
+            }
+            let name_start = s.len();
+            write_thread_name(s);
+            let len = s[name_start..].chars().count();
+            s.extend(::std::iter::repeat(' ').take(self.thread_width.saturating_sub(len)));
+        }
+        if s.len() > start && (self.include_level || self.include_module_path) {
+            s.push(' ');
+        }
+        if self.include_level {
+            s.push_str(level.as_str());
+        }
+        if self.include_module_path {
             let pth = record.module_path().unwrap_or(MODULE_PATH_UNKNOWN);
             if self.include_level {
-                format!(" [{}]", pth)
-            } else {
-                pth.into()
-            }
-        } else {
-            String::new()
-        };
-        let line_text = if self.include_line_numbers {
-            if let Some(l) = record.line() {
-                format!(" (line {})", l)
+                s.push_str(" [");
+                s.push_str(pth);
+                s.push(']');
             } else {
-                String::new()
+                s.push_str(pth);
             }
-        } else {
-            String::new()
-        };
-        let mut tag = format!("{}{}{}", level_text, module_path_text, line_text);
-        if self.colors {
-            tag = self.select_color(&level).paint(tag).to_string();
         }
-        tag
+        let line = if self.include_line_numbers { record.line() } else { None };
+        match (self.select_file(record), line) {
+            (Some(f), l) => {
+                if s.len() > start {
+                    s.push(' ');
+                }
+                match l {
+                    Some(l) if self.compact_location => write!(s, "{}:{}", f, l),
+                    Some(l) => write!(s, "({}, line {})", f, l),
+                    None if self.compact_location => write!(s, "{}", f),
+                    None => write!(s, "({})", f),
+                }.expect("Writing to a string");
+            },
+            (None, Some(l)) => write!(s, " (line {})", l).expect("Writing to a string"),
+            (None, None) => {},
+        }
+        if colorize {
+            write!(s, "{}", style.suffix()).expect("Writing to a string");
+        }
+    }
+
+    /// Gets the path of the source file, trimmed according to the mode, if it is included.
+    fn select_file<'a>(&self, record: &log::Record<'a>) -> Option<&'a str> {
+        let p = self.file_path?;
+        record.file().map(|f| p.trim(f))
+    }
+
+    /// Gets the name of the host, which is resolved at initialization, or once for the process if
+    /// the logger is used without being initialized.
+    fn resolved_hostname(&self) -> &str {
+        match self.hostname {
+            Some(ref h) => h,
+            None => host::cached_hostname(),
+        }
+    }
+
+    /// Appends the whole log statement, including the trailing newline.
+    fn write_record(&self, s: &mut String, record: &log::Record) {
+        match self.format {
+            Format::Text => {
+                let colorize = self.should_colorize(&record.level());
+                self.write_tag(s, record, colorize);
+                if colorize {
+                    let style = self.select_message_style(&record.level());
+                    write!(s, "{}{}{}{}", style.prefix(), self.separator, record.args(), style.suffix())
+                } else {
+                    write!(s, "{}{}", self.separator, record.args())
+                }.expect("Writing to a string");
+                let pairs = self.key_values.collect(record);
+                self.key_values.write_text(s, &pairs, colorize, self.color_depth);
+            },
+            Format::Json => format::write_json(s, self, record),
+            Format::Logfmt => format::write_logfmt(s, self, record),
+            // The template is only parsed when the logger is used without being initialized.
+            Format::Template(ref t) => match self.template {
+                Some(ref template) => template.render(s, self, record),
+                None => Template::parse(t).render(s, self, record),
+            },
+        }
+        s.push('\n');
     }
+
+    /// Formats the whole log statement into a new string, including the trailing newline.
+    fn format_record(&self, record: &log::Record) -> String {
+        let mut line = String::with_capacity(128);
+        self.write_record(&mut line, record);
+        line
+    }
+}
+
+/// Appends the name of the current thread, or its id if it has no name.
+fn write_thread_name(s: &mut String) {
+    let thread = ::std::thread::current();
+    match thread.name() {
+        Some(name) => s.push_str(name),
+        // The id is only exposed through `Debug`, so it is formatted once per thread.
+        None => {
+            if THREAD_ID.try_with(|id| s.push_str(id)).is_err() {
+                write!(s, "{:?}", thread.id()).expect("Writing to a string");
+            }
+        },
+    }
+}
+
+thread_local! {
+    static THREAD_ID: String = format!("{:?}", ::std::thread::current().id());
+
+    /// The buffer of each thread for formatting log statements, which is reused so logging does
+    /// not allocate once the buffer has grown to the usual length of a log statement.
+    static BUFFER: RefCell<String> = RefCell::new(String::with_capacity(DEFAULT_BUFFER_CAPACITY));
 }
 
+const DEFAULT_BUFFER_CAPACITY: usize = 256;
+
+/// The largest buffer that is kept after an unusually long log statement.
+const MAX_BUFFER_CAPACITY: usize = 64 * 1024;
+
 impl log::Log for Logger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        metadata.level() <= self.level
+        metadata.level() <= self.level_for(metadata.target())
     }
 
     fn log(&self, record: &log::Record) {
@@ -810,29 +1982,46 @@ impl log::Log for Logger {
             if !self.module_path_filters.is_empty() && !self.module_path_filters.iter().any(|filter| record.module_path().unwrap_or(MODULE_PATH_UNKNOWN).starts_with(filter)) {
                 return
             }
-            match self.select_output(&record.level()) {
-                Output::Stderr => {
-                    writeln!(
-                        &mut io::stderr(),
-                        "{}{}{}",
-                        self.create_tag(&record),
-                        self.separator,
-                        record.args()
-                    ).expect("Writing to stderr");
-                },
-                Output::Stdout => {
-                    println!(
-                        "{}{}{}",
-                        self.create_tag(&record),
-                        self.separator,
-                        record.args()
-                    );
+            if capture::active() && capture::record(self, record) {
+                return;
+            }
+            let output = self.select_output(&record.level());
+            if let Some(ref worker) = self.worker {
+                let mut line = worker.buffer();
+                self.write_record(&mut line, record);
+                worker.send(output.clone(), line);
+                return;
+            }
+            // The whole line is written at once, so lines of concurrent threads never interleave.
+            let reused = BUFFER.try_with(|b| match b.try_borrow_mut() {
+                Ok(mut buffer) => {
+                    buffer.clear();
+                    self.write_record(&mut buffer, record);
+                    self.write_errors.write(output, buffer.as_bytes(), false);
+                    if buffer.capacity() > MAX_BUFFER_CAPACITY {
+                        *buffer = String::with_capacity(DEFAULT_BUFFER_CAPACITY);
+                    }
+                    true
                 },
+                Err(_) => false,
+            });
+            // The buffer is in use if formatting the message logs, and gone while the thread exits.
+            if reused != Ok(true) {
+                self.write_errors.write(output, self.format_record(record).as_bytes(), false);
             }
         }
     }
+
     fn flush(&self) {
-        // println! flushes by itself
+        if let Some(ref worker) = self.worker {
+            worker.drain();
+        }
+        // stderr is unbuffered and stdout is line buffered, but a writer may buffer arbitrarily.
+        for level in &[&self.error, &self.warn, &self.info, &self.debug, &self.trace] {
+            if let Output::Writer(ref w) = level.output {
+                let _ = w.lock().unwrap_or_else(|e| e.into_inner()).flush();
+            }
+        }
     }
 }
 
@@ -870,26 +2059,76 @@ mod tests {
     use ansi_term::Colour;
     use super::*;
 
+    /// Writes the log statements of all levels of the logger to a buffer.
+    fn captured(logger: Logger) -> (Logger, Arc<Mutex<Vec<u8>>>) {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let logger = log::Level::iter().fold(logger, |l, level| l.output(&level, Output::Writer(buffer.clone())));
+        (logger, buffer)
+    }
+
+    /// Gets the log statements written to the buffer.
+    fn contents(buffer: &Arc<Mutex<Vec<u8>>>) -> String {
+        String::from_utf8(buffer.lock().unwrap().clone()).unwrap()
+    }
+
     #[test]
     fn defaults_are_correct() {
         let logger = Logger::new();
         assert_eq!(logger.include_level, DEFAULT_INCLUDE_LEVEL);
         assert_eq!(logger.include_line_numbers, DEFAULT_INCLUDE_LINE_NUMBERS);
         assert_eq!(logger.include_module_path, DEFAULT_INCLUDE_MODULE_PATH);
-        assert_eq!(logger.colors, DEFAULT_COLORS && atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr));
+        assert_eq!(logger.color_choice, ColorChoice::Auto);
+        assert_eq!(logger.stderr_colors, DEFAULT_COLORS && ColorChoice::Auto.resolve(atty::is(atty::Stream::Stderr)));
+        assert_eq!(logger.stdout_colors, DEFAULT_COLORS && ColorChoice::Auto.resolve(atty::is(atty::Stream::Stdout)));
         assert_eq!(logger.level, DEFAULT_LEVEL);
         assert_eq!(logger.separator, String::from(DEFAULT_SEPARATOR));
-        assert_eq!(logger.error.color, DEFAULT_ERROR_COLOR);
-        assert_eq!(logger.warn.color, DEFAULT_WARN_COLOR);
-        assert_eq!(logger.info.color, DEFAULT_INFO_COLOR);
-        assert_eq!(logger.debug.color, DEFAULT_DEBUG_COLOR);
-        assert_eq!(logger.trace.color, DEFAULT_TRACE_COLOR);
+        assert_eq!(logger.error.style, Style::from(DEFAULT_ERROR_COLOR));
+        assert!(logger.error.message_style.is_plain());
+        assert_eq!(logger.warn.style, Style::from(DEFAULT_WARN_COLOR));
+        assert!(logger.warn.message_style.is_plain());
+        assert_eq!(logger.info.style, Style::from(DEFAULT_INFO_COLOR));
+        assert!(logger.info.message_style.is_plain());
+        assert_eq!(logger.debug.style, Style::from(DEFAULT_DEBUG_COLOR));
+        assert!(logger.debug.message_style.is_plain());
+        assert_eq!(logger.trace.style, Style::from(DEFAULT_TRACE_COLOR));
+        assert!(logger.trace.message_style.is_plain());
+    }
+
+    #[test]
+    fn env_works() {
+        let logger = Logger::from_env("LOGGERV_TEST_LOG");
+        assert_eq!(logger.env, Some(String::from("LOGGERV_TEST_LOG")));
     }
 
     #[test]
     fn color_works() {
         let logger = Logger::new().color(&log::Level::Trace, Colour::Fixed(11));
-        assert_eq!(logger.trace.color, Colour::Fixed(11));
+        assert_eq!(logger.trace.style.foreground(), Some(Color::Fixed(11)));
+        let logger = logger
+            .style(&log::Level::Trace, Style::new().bold())
+            .color(&log::Level::Trace, Color::Rgb(255, 136, 0));
+        assert_eq!(logger.trace.style, Style::new().fg(Color::Rgb(255, 136, 0)).bold());
+    }
+
+    #[test]
+    fn theme_works() {
+        let theme = Theme::named("high-contrast").unwrap();
+        let logger = Logger::new().theme(&theme);
+        assert_eq!(logger.error.style, theme.style_for(&log::Level::Error));
+        assert_eq!(logger.trace.style, theme.style_for(&log::Level::Trace));
+        let logger = Logger::new();
+        assert_eq!(logger.clone().theme(&Theme::named("default").unwrap()), logger);
+    }
+
+    #[test]
+    fn style_works() {
+        let style = Style::new().fg(Color::Red).bold();
+        let logger = Logger::new()
+            .style(&log::Level::Error, style)
+            .message_style(&log::Level::Error, style);
+        assert_eq!(logger.error.style, style);
+        assert_eq!(logger.error.message_style, style);
+        assert!(logger.warn.message_style.is_plain());
     }
 
     #[test]
@@ -899,16 +2138,42 @@ mod tests {
         assert_eq!(logger.separator, EXPECTED);
     }
 
+    #[test]
+    fn format_works() {
+        let logger = Logger::new().format(Format::Json);
+        assert_eq!(logger.format, Format::Json);
+        let logger = Logger::new().format(Format::Logfmt);
+        assert_eq!(logger.format, Format::Logfmt);
+    }
+
+    #[test]
+    fn template_works() {
+        let logger = Logger::new().template("{level} {msg}");
+        assert_eq!(logger.format, Format::Template(String::from("{level} {msg}")));
+    }
+
+    #[test]
+    fn color_choice_works() {
+        let logger = Logger::new().color_choice(ColorChoice::Always);
+        assert_eq!(logger.color_choice, ColorChoice::Always);
+        assert!(logger.stderr_colors && logger.stdout_colors);
+        assert!(logger.should_colorize(&log::Level::Error));
+        let logger = logger.output(&log::Level::Error, Output::writer(io::sink()));
+        assert!(logger.should_colorize(&log::Level::Error));
+    }
+
     #[test]
     fn colors_works() {
         let logger = Logger::new().colors(false);
-        assert!(!logger.colors);
+        assert_eq!(logger.color_choice, ColorChoice::Never);
+        assert!(!logger.stderr_colors && !logger.stdout_colors);
     }
 
     #[test]
     fn no_colors_works() {
         let logger = Logger::new().no_colors();
-        assert!(!logger.colors);
+        assert_eq!(logger.color_choice, ColorChoice::Never);
+        assert!(!logger.stderr_colors && !logger.stdout_colors);
     }
 
     #[test]
@@ -923,6 +2188,82 @@ mod tests {
         assert!(logger.include_level);
     }
 
+    #[test]
+    fn file_works() {
+        let tag = |logger: Logger| {
+            let mut tag = String::new();
+            logger.write_tag(&mut tag, &log::Record::builder()
+                .module_path(Some("app::net"))
+                .file(Some("crates/app/src/net.rs"))
+                .line(Some(42))
+                .build(), false);
+            tag
+        };
+        assert_eq!(tag(Logger::new().file(FilePath::Full)), "app::net (crates/app/src/net.rs)");
+        assert_eq!(tag(Logger::new().file(FilePath::CrateRelative).line_numbers(true)), "app::net (src/net.rs, line 42)");
+        assert_eq!(tag(Logger::new().file(FilePath::Basename).line_numbers(true).compact_location(true)), "app::net net.rs:42");
+        assert_eq!(tag(Logger::new().file(FilePath::Basename).no_module_path().compact_location(true)), "net.rs");
+        assert_eq!(tag(Logger::new().file(FilePath::Full).no_file().line_numbers(true)), "app::net (line 42)");
+    }
+
+    #[test]
+    fn hostname_and_pid_work() {
+        let logger = Logger::new().hostname(true).pid(true).level(true).no_module_path();
+        assert!(logger.include_hostname && logger.include_pid);
+        let logger = logger.prepare();
+        let hostname = logger.hostname.clone().unwrap();
+        let mut tag = String::new();
+        logger.write_tag(&mut tag, &log::Record::builder().level(log::Level::Info).build(), false);
+        assert_eq!(tag, format!("{}[{}] INFO", hostname, ::std::process::id()));
+        let logger = Logger::new().pid(true).no_module_path().prepare();
+        assert!(logger.hostname.is_none());
+        tag.clear();
+        logger.write_tag(&mut tag, &log::Record::builder().build(), false);
+        assert_eq!(tag, format!("[{}]", ::std::process::id()));
+        let logger = Logger::new().template("{hostname} {msg}").prepare();
+        assert_eq!(logger.hostname, Some(hostname));
+    }
+
+    #[test]
+    fn thread_works() {
+        let logger = Logger::new().thread(true).thread_width(8);
+        assert!(logger.include_thread);
+        assert_eq!(logger.thread_width, 8);
+        let handle = std::thread::Builder::new().name(String::from("pool-1")).spawn(move || {
+            let mut tag = String::new();
+            logger.level(true).write_tag(&mut tag, &log::Record::builder()
+                .level(log::Level::Warn)
+                .module_path(Some("app"))
+                .build(), false);
+            tag
+        }).unwrap();
+        assert_eq!(handle.join().unwrap(), "pool-1   WARN [app]");
+        let logger = Logger::new().thread(true).no_module_path();
+        let handle = std::thread::spawn(move || {
+            let mut tag = String::new();
+            logger.write_tag(&mut tag, &log::Record::builder().build(), false);
+            (tag, format!("{:?}", std::thread::current().id()))
+        });
+        let (tag, id) = handle.join().unwrap();
+        assert_eq!(tag, id);
+    }
+
+    #[test]
+    fn timestamp_works() {
+        let logger = Logger::new().timestamp(Timestamp::time());
+        assert_eq!(logger.timestamp, Some(Timestamp::time()));
+        let logger = logger.no_timestamp();
+        assert!(logger.timestamp.is_none());
+    }
+
+    #[test]
+    fn elapsed_works() {
+        let logger = Logger::new().elapsed(Elapsed::SincePrevious);
+        assert_eq!(logger.elapsed, Some(Elapsed::SincePrevious));
+        let logger = logger.no_elapsed();
+        assert!(logger.elapsed.is_none());
+    }
+
     #[test]
     fn max_level_works() {
         let logger = Logger::new().max_level(log::Level::Trace);
@@ -930,6 +2271,30 @@ mod tests {
         assert!(logger.verbosity.is_none());
     }
 
+    #[test]
+    fn directive_works() {
+        let logger = Logger::new().directive("app", log::LevelFilter::Debug);
+        assert_eq!(logger.directives, vec![Directive::new("app", log::LevelFilter::Debug)]);
+    }
+
+    #[test]
+    fn directives_works() {
+        let logger = Logger::new()
+            .max_level(log::Level::Warn)
+            .directives("app=debug,hyper=off,app::db=trace");
+        assert_eq!(logger.directives.len(), 3);
+        assert_eq!(logger.max_level_filter(), log::LevelFilter::Trace);
+        let enabled = |level, target| {
+            log::Log::enabled(&logger, &log::Metadata::builder().level(level).target(target).build())
+        };
+        assert!(enabled(log::Level::Trace, "app::db::pool"));
+        assert!(enabled(log::Level::Debug, "app::net"));
+        assert!(!enabled(log::Level::Trace, "app::net"));
+        assert!(!enabled(log::Level::Error, "hyper::client"));
+        assert!(enabled(log::Level::Warn, "other"));
+        assert!(!enabled(log::Level::Info, "other"));
+    }
+
     #[test]
     fn base_level_works() {
         let logger = Logger::new().base_level(log::Level::Info);
@@ -969,6 +2334,91 @@ mod tests {
         assert_eq!(logger.trace.output, Output::Stderr);
     }
 
+    #[test]
+    fn writer_output_works() {
+        let (logger, buffer) = captured(Logger::new().no_colors());
+        log::Log::log(&logger, &log::Record::builder()
+            .level(log::Level::Warn)
+            .module_path(Some("app::net"))
+            .args(format_args!("retrying"))
+            .build());
+        assert_eq!(contents(&buffer), "app::net: retrying\n");
+    }
+
+    #[test]
+    fn colorized_tag_works() {
+        let (logger, buffer) = captured(Logger::new()
+            .color_choice(ColorChoice::Always)
+            .level(true)
+            .line_numbers(true));
+        log::Log::log(&logger, &log::Record::builder()
+            .level(log::Level::Error)
+            .module_path(Some("app"))
+            .line(Some(7))
+            .args(format_args!("failed"))
+            .build());
+        let expected = format!("{}: failed\n", DEFAULT_ERROR_COLOR.paint("ERROR [app] (line 7)"));
+        assert_eq!(contents(&buffer), expected);
+    }
+
+    #[test]
+    fn key_values_works() {
+        let (logger, buffer) = captured(Logger::new().key_values(KeyValues::new().hide("token").order(vec!["id"])));
+        let kvs: [(&str, log::kv::Value); 3] = [
+            ("user", log::kv::Value::from("a b")),
+            ("token", log::kv::Value::from("secret")),
+            ("id", log::kv::Value::from(42)),
+        ];
+        log::Log::log(&logger, &log::Record::builder()
+            .level(log::Level::Error)
+            .module_path(Some("app"))
+            .args(format_args!("login"))
+            .key_values(&kvs)
+            .build());
+        assert_eq!(contents(&buffer), "app: login id=42 user=\"a b\"\n");
+    }
+
+    #[test]
+    fn reentrant_logging_works() {
+        struct Nested<'a>(&'a Logger);
+
+        impl<'a> fmt::Display for Nested<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                log::Log::log(self.0, &log::Record::builder()
+                    .level(log::Level::Warn)
+                    .module_path(Some("inner"))
+                    .args(format_args!("nested"))
+                    .build());
+                f.write_str("outer")
+            }
+        }
+
+        let (logger, buffer) = captured(Logger::new().no_colors());
+        log::Log::log(&logger, &log::Record::builder()
+            .level(log::Level::Warn)
+            .module_path(Some("app"))
+            .args(format_args!("{}", Nested(&logger)))
+            .build());
+        assert_eq!(contents(&buffer), "inner: nested\napp: outer\n");
+    }
+
+    #[test]
+    fn background_works() {
+        let (logger, buffer) = captured(Logger::new().no_colors().background(Background::new().capacity(1)));
+        let logger = logger.prepare();
+        assert!(logger.worker.is_some());
+        for i in 0..3 {
+            log::Log::log(&logger, &log::Record::builder()
+                .level(log::Level::Warn)
+                .module_path(Some("app"))
+                .args(format_args!("attempt {}", i))
+                .build());
+        }
+        log::Log::flush(&logger);
+        assert_eq!(contents(&buffer), "app: attempt 0\napp: attempt 1\napp: attempt 2\n");
+        assert!(Logger::new().no_background().prepare().worker.is_none());
+    }
+
     #[test]
     fn init_works() {
         let result = Logger::new().init();
@@ -976,13 +2426,51 @@ mod tests {
     }
 
     #[test]
-    fn select_color_works() {
+    fn should_colorize_works() {
+        let mut logger = Logger::new()
+            .output(&log::Level::Error, Output::Stderr)
+            .output(&log::Level::Info, Output::Stdout)
+            .output(&log::Level::Debug, Output::writer(io::sink()));
+        logger.stderr_colors = true;
+        logger.stdout_colors = false;
+        logger.writer_colors = false;
+        assert!(logger.should_colorize(&log::Level::Error));
+        assert!(!logger.should_colorize(&log::Level::Info));
+        assert!(!logger.should_colorize(&log::Level::Debug));
+    }
+
+    #[test]
+    fn clicolor_force_works() {
+        let mut logger = Logger::new().output(&log::Level::Error, Output::writer(io::sink()));
+        logger.resolve_colors(|_| None);
+        assert!(!logger.should_colorize(&log::Level::Error));
+        logger.resolve_colors(|name| if name == "CLICOLOR_FORCE" { Some(String::from("1")) } else { None });
+        assert!(logger.should_colorize(&log::Level::Error));
+        assert!(logger.stderr_colors && logger.stdout_colors);
+        let mut logger = logger.no_colors();
+        logger.resolve_colors(|name| if name == "CLICOLOR_FORCE" { Some(String::from("1")) } else { None });
+        assert!(!logger.should_colorize(&log::Level::Error));
+    }
+
+    #[test]
+    fn color_depth_works() {
+        let logger = Logger::new()
+            .color(&log::Level::Error, Color::Rgb(255, 136, 0))
+            .color_depth(ColorDepth::Ansi256);
+        assert_eq!(logger.select_style(&log::Level::Error), Colour::Fixed(208).normal());
+        let logger = logger.color_depth(ColorDepth::TrueColor);
+        assert_eq!(logger.select_style(&log::Level::Error), Colour::RGB(255, 136, 0).normal());
+    }
+
+    #[test]
+    fn select_style_works() {
         let logger = Logger::new();
-        assert_eq!(logger.select_color(&log::Level::Error), DEFAULT_ERROR_COLOR);
-        assert_eq!(logger.select_color(&log::Level::Warn), DEFAULT_WARN_COLOR);
-        assert_eq!(logger.select_color(&log::Level::Info), DEFAULT_INFO_COLOR);
-        assert_eq!(logger.select_color(&log::Level::Debug), DEFAULT_DEBUG_COLOR);
-        assert_eq!(logger.select_color(&log::Level::Trace), DEFAULT_TRACE_COLOR);
+        assert_eq!(logger.select_style(&log::Level::Error), DEFAULT_ERROR_COLOR.normal());
+        assert_eq!(logger.select_style(&log::Level::Warn), DEFAULT_WARN_COLOR.normal());
+        assert_eq!(logger.select_style(&log::Level::Info), DEFAULT_INFO_COLOR.normal());
+        assert_eq!(logger.select_style(&log::Level::Debug), DEFAULT_DEBUG_COLOR.normal());
+        assert_eq!(logger.select_style(&log::Level::Trace), DEFAULT_TRACE_COLOR.normal());
+        assert!(logger.select_message_style(&log::Level::Error).is_plain());
     }
 }
 
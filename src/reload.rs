@@ -0,0 +1,309 @@
+//! Reloading the configuration from a file on SIGHUP or when the file changes.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use super::{Logger, LoggerHandle};
+#[cfg(feature = "config")]
+use super::LoggerConfig;
+use spec;
+
+type Parser = Arc<dyn Fn(Logger, &str) -> Result<Logger, String> + Send + Sync>;
+
+const DEFAULT_RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reloads the configuration of an initialized logger from a file.
+///
+/// The file is read when the process receives `SIGHUP` (on Unix) or when its modification time
+/// or size changes, which is checked at an interval. The configuration from the file is applied
+/// on top of a base logger, usually a clone of the logger before initialization, and then
+/// replaces the active logger through the handle. A log statement that is being written while the
+/// logger is replaced finishes with the old configuration; all later ones use the new one.
+///
+/// By default, the file contains the same entries as the environment variable of `Logger::env`,
+/// separated by commas or newlines, with a `#` after whitespace starting a comment. A different
+/// syntax can be used with the `parser` method. If the file cannot be read or parsed, the active
+/// logger is kept and the error is written to stderr, since the active logger may filter it out.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use loggerv::Reloader;
+///
+/// fn main() {
+///     let logger = loggerv::Logger::new().verbosity(1);
+///     let handle = logger.clone().init_with_handle().unwrap();
+///     Reloader::new(handle, "/etc/myapp/logging.conf", logger)
+///         .spawn()
+///         .unwrap();
+///
+///     info!("This is printed until `debug` is written to the file and `kill -HUP` is sent");
+/// }
+/// ```
+pub struct Reloader {
+    handle: LoggerHandle,
+    path: PathBuf,
+    base: Logger,
+    interval: Duration,
+    sighup: bool,
+    parser: Parser,
+}
+
+impl Reloader {
+    /// Creates a reloader for the file, which applies its configuration on top of the base logger.
+    pub fn new<P: AsRef<Path>>(handle: LoggerHandle, path: P, base: Logger) -> Reloader {
+        Reloader {
+            handle,
+            path: path.as_ref().to_path_buf(),
+            base,
+            interval: DEFAULT_RELOAD_INTERVAL,
+            sighup: true,
+            parser: Arc::new(|base, contents| Ok(spec::apply(base, contents))),
+        }
+    }
+
+    /// Sets the interval to check the file for changes. The default is one second.
+    pub fn interval(mut self, d: Duration) -> Self {
+        self.interval = d;
+        self
+    }
+
+    /// Enables or disables reloading on `SIGHUP`. The default is enabled. This has no effect on
+    /// platforms without signals.
+    pub fn sighup(mut self, s: bool) -> Self {
+        self.sighup = s;
+        self
+    }
+
+    /// Sets the function that creates a logger from the base logger and the contents of the file.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// extern crate log;
+    /// extern crate loggerv;
+    ///
+    /// use loggerv::Reloader;
+    ///
+    /// fn main() {
+    ///     let logger = loggerv::Logger::new();
+    ///     let handle = logger.clone().init_with_handle().unwrap();
+    ///     Reloader::new(handle, "/etc/myapp/level", logger)
+    ///         .parser(|base, contents| {
+    ///             contents.trim().parse::<log::Level>()
+    ///                 .map(|l| base.max_level(l))
+    ///                 .map_err(|e| e.to_string())
+    ///         })
+    ///         .spawn()
+    ///         .unwrap();
+    /// }
+    /// ```
+    pub fn parser<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Logger, &str) -> Result<Logger, String> + Send + Sync + 'static,
+    {
+        self.parser = Arc::new(f);
+        self
+    }
+
+    /// Reads the file as a TOML `LoggerConfig` instead of the syntax of the environment variable.
+    ///
+    /// This requires the `config` feature, which is enabled by default.
+    #[cfg(feature = "config")]
+    pub fn toml(self) -> Self {
+        self.parser(|base, contents| {
+            LoggerConfig::from_toml(contents)
+                .and_then(|c| c.apply(base))
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    /// Reads the file and replaces the active logger now.
+    pub fn reload(&self) -> Result<(), ReloadError> {
+        let contents = fs::read_to_string(&self.path).map_err(ReloadError::Io)?;
+        let logger = (self.parser)(self.base.clone(), &contents).map_err(ReloadError::Parse)?;
+        self.handle.replace(logger);
+        Ok(())
+    }
+
+    /// Reads the file now, if it exists, and then watches it on a background thread until the
+    /// thread is stopped. Dropping the returned `ReloadThread` keeps watching for the rest of the
+    /// process.
+    pub fn spawn(self) -> io::Result<ReloadThread> {
+        let hangup = Arc::new(AtomicBool::new(false));
+        if self.sighup {
+            register_sighup(&hangup)?;
+        }
+        let mut last = modified(&self.path);
+        if last.is_some() {
+            self.report(self.reload());
+        }
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stopped = Arc::clone(&stopped);
+            thread::Builder::new()
+                .name(String::from("loggerv-reload"))
+                .spawn(move || loop {
+                    thread::park_timeout(self.interval);
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let current = modified(&self.path);
+                    let changed = current.is_some() && current != last;
+                    if hangup.swap(false, Ordering::SeqCst) || changed {
+                        last = current;
+                        self.report(self.reload());
+                    }
+                })?
+        };
+        Ok(ReloadThread { thread, stopped })
+    }
+
+    fn report(&self, result: Result<(), ReloadError>) {
+        if let Err(e) = result {
+            // The active logger may filter the error out, or be the reason it failed.
+            let _ = writeln!(io::stderr(), "loggerv: failed to reload {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl fmt::Debug for Reloader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Reloader")
+            .field("path", &self.path)
+            .field("interval", &self.interval)
+            .field("sighup", &self.sighup)
+            .finish()
+    }
+}
+
+/// The background thread of `Reloader::spawn`, which watches the file until it is stopped.
+#[derive(Debug)]
+pub struct ReloadThread {
+    thread: thread::JoinHandle<()>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl ReloadThread {
+    /// Stops watching the file and waits for the thread to finish a reload in progress. The active
+    /// logger is kept.
+    pub fn stop(self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.thread.thread().unpark();
+        let _ = self.thread.join();
+    }
+}
+
+/// The error of reloading the configuration.
+#[derive(Debug)]
+pub enum ReloadError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The contents of the file were rejected by the parser.
+    Parse(String),
+}
+
+impl fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReloadError::Io(ref e) => write!(f, "{}", e),
+            ReloadError::Parse(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl ::std::error::Error for ReloadError {}
+
+/// Gets the modification time and size of the file, which together identify a change.
+fn modified(path: &Path) -> Option<(SystemTime, u64)> {
+    fs::metadata(path).ok().and_then(|m| m.modified().ok().map(|t| (t, m.len())))
+}
+
+#[cfg(unix)]
+fn register_sighup(hangup: &Arc<AtomicBool>) -> io::Result<()> {
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(hangup)).map(|_| ())
+}
+
+#[cfg(not(unix))]
+fn register_sighup(_: &Arc<AtomicBool>) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util;
+
+    fn temp_path(test: &str) -> PathBuf {
+        test_util::temp_path(test, "logging.conf")
+    }
+
+    #[test]
+    fn reload_works() {
+        let path = temp_path("reload");
+        fs::write(&path, "debug\n+level # show the level\n").unwrap();
+        let base = Logger::new();
+        let handle = LoggerHandle::new(base.clone());
+        let reloader = Reloader::new(handle.clone(), &path, base).sighup(false);
+        reloader.reload().unwrap();
+        assert!(handle.read().include_level);
+        assert_eq!(handle.directives().len(), 1);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn toml_works() {
+        let path = temp_path("reload-toml");
+        fs::write(&path, "include_level = true\nlevel = \"debug\"").unwrap();
+        let base = Logger::new();
+        let handle = LoggerHandle::new(base.clone());
+        Reloader::new(handle.clone(), &path, base).sighup(false).toml().reload().unwrap();
+        assert!(handle.read().include_level);
+        assert_eq!(handle.max_level(), log::Level::Debug);
+    }
+
+    #[test]
+    fn reload_errors_keep_the_logger() {
+        let path = temp_path("reload-error");
+        let base = Logger::new();
+        let handle = LoggerHandle::new(base.clone().level(true));
+        let reloader = Reloader::new(handle.clone(), &path, base)
+            .sighup(false)
+            .parser(|_, _| Err(String::from("nope")));
+        match reloader.reload() {
+            Err(ReloadError::Io(_)) => {},
+            r => panic!("unexpected {:?}", r),
+        }
+        fs::write(&path, "").unwrap();
+        match reloader.reload() {
+            Err(ReloadError::Parse(ref e)) if e == "nope" => {},
+            r => panic!("unexpected {:?}", r),
+        }
+        assert!(handle.read().include_level);
+    }
+
+    #[test]
+    fn stop_works() {
+        let path = temp_path("reload-stop");
+        let base = Logger::new();
+        let handle = LoggerHandle::new(base.clone());
+        let thread = Reloader::new(handle, &path, base)
+            .sighup(false)
+            .interval(Duration::from_secs(60))
+            .spawn()
+            .unwrap();
+        // The thread is woken up instead of finishing the interval, so this returns right away.
+        let start = ::std::time::Instant::now();
+        thread.stop();
+        assert!(start.elapsed() < Duration::from_secs(60));
+    }
+}
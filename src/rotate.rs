@@ -0,0 +1,230 @@
+//! A file writer that rotates its file by size or by day.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+const DEFAULT_KEEP: usize = 5;
+
+/// A log file that is rotated when it grows beyond a size or when the day changes.
+///
+/// The active file is always written at the configured path. When the file is rotated, it is
+/// renamed to `<path>.1`, the previous `<path>.1` to `<path>.2`, and so on. Only the configured
+/// number of old generations are kept; older ones are removed. Days are counted in UTC.
+///
+/// A `RotatingFile` is used as an output with `Output::writer`, so it can be combined with the
+/// other outputs per level.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use log::Level;
+/// use loggerv::{Output, RotatingFile};
+///
+/// fn main() {
+///     let file = RotatingFile::new(std::env::temp_dir().join("loggerv-rotate-example.log"))
+///         .max_size(10 * 1024 * 1024)
+///         .daily(true)
+///         .keep(3)
+///         .open()
+///         .unwrap();
+///     let output = Output::writer(file);
+///     loggerv::Logger::new()
+///         .output(&Level::Warn, output.clone())
+///         .output(&Level::Info, output)
+///         .verbosity(1)
+///         .init()
+///         .unwrap();
+///
+///     error!("This is printed to stderr");
+///     warn!("This is written to the rotating file");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: Option<u64>,
+    daily: bool,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+    day: u64,
+}
+
+impl RotatingFile {
+    /// Creates a rotating file at the path.
+    ///
+    /// By default, the file is never rotated. The file is opened when the first log statement is
+    /// written, unless `open` is used.
+    pub fn new<P: AsRef<Path>>(path: P) -> RotatingFile {
+        RotatingFile {
+            path: path.as_ref().to_path_buf(),
+            max_size: None,
+            daily: false,
+            keep: DEFAULT_KEEP,
+            file: None,
+            size: 0,
+            day: 0,
+        }
+    }
+
+    /// Rotates the file before it would grow beyond the size in bytes.
+    ///
+    /// A single log statement larger than the size is still written to an empty file.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Enables or disables rotating the file when the (UTC) day changes.
+    pub fn daily(mut self, d: bool) -> Self {
+        self.daily = d;
+        self
+    }
+
+    /// Sets the number of old generations to keep. The default is 5.
+    ///
+    /// With zero, the file is truncated on rotation.
+    pub fn keep(mut self, n: usize) -> Self {
+        self.keep = n;
+        self
+    }
+
+    /// Opens the file immediately, so an error is reported now instead of on the first write.
+    pub fn open(mut self) -> io::Result<Self> {
+        self.open_file()?;
+        Ok(self)
+    }
+
+    /// Gets the path of a generation, where generation zero is the active file.
+    pub fn generation_path(&self, generation: usize) -> PathBuf {
+        if generation == 0 {
+            return self.path.clone();
+        }
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", generation));
+        PathBuf::from(name)
+    }
+
+    fn open_file(&mut self) -> io::Result<()> {
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let metadata = file.metadata()?;
+        self.size = metadata.len();
+        // An existing file keeps the day it was last written to, so a log file left over from
+        // yesterday is rotated on the first write today.
+        self.day = metadata.modified().map(day_of).unwrap_or_else(|_| today());
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn should_rotate(&self, len: usize) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        let too_big = self.max_size.is_some_and(|max| self.size + len as u64 > max);
+        let new_day = self.daily && today() != self.day;
+        too_big || new_day
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = self.generation_path(self.keep);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for generation in (1..self.keep).rev() {
+                let from = self.generation_path(generation);
+                if from.exists() {
+                    fs::rename(&from, self.generation_path(generation + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.generation_path(1))?;
+        }
+        self.open_file()
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.file.is_none() {
+            self.open_file()?;
+        }
+        if self.should_rotate(buf.len()) {
+            self.rotate()?;
+        }
+        let file = self.file.as_mut().expect("Rotating file is open");
+        file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        self.day = today();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file {
+            Some(ref mut file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+fn day_of(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).map(|d| d.as_secs() / SECONDS_PER_DAY).unwrap_or(0)
+}
+
+fn today() -> u64 {
+    day_of(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util;
+
+    fn temp_path(test: &str) -> PathBuf {
+        test_util::temp_path(test, "app.log")
+    }
+
+    #[test]
+    fn rotates_by_size() {
+        let path = temp_path("size");
+        let mut file = RotatingFile::new(&path).max_size(10).keep(2);
+        for line in &["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(fs::read_to_string(file.generation_path(1)).unwrap(), "third\n");
+        assert_eq!(fs::read_to_string(file.generation_path(2)).unwrap(), "second\n");
+        assert!(!file.generation_path(3).exists());
+    }
+
+    #[test]
+    fn keep_zero_truncates() {
+        let path = temp_path("truncate");
+        let mut file = RotatingFile::new(&path).max_size(10).keep(0);
+        file.write_all(b"first\n").unwrap();
+        file.write_all(b"second\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        assert!(!file.generation_path(1).exists());
+    }
+
+    #[test]
+    fn rotates_daily() {
+        let path = temp_path("daily");
+        let mut file = RotatingFile::new(&path).daily(true).open().unwrap();
+        file.write_all(b"yesterday\n").unwrap();
+        file.day -= 1;
+        file.write_all(b"today\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "today\n");
+        assert_eq!(fs::read_to_string(file.generation_path(1)).unwrap(), "yesterday\n");
+    }
+}
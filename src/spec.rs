@@ -0,0 +1,214 @@
+//! The textual configuration of a logger, e.g. from an environment variable.
+//!
+//! A spec is a list of entries, separated by commas or newlines, where a `#` at the start of a
+//! line or after whitespace starts a comment until the end of the line:
+//!
+//! - A level, e.g. `debug`, or a directive, e.g. `myapp::db=trace`.
+//! - `color=auto`, `color=always`, or `color=never`.
+//! - `format=text`, `format=json`, or `format=logfmt`.
+//! - `theme=<name>`, e.g. `theme=solarized`. See `Theme` for the names.
+//! - `file=full`, `file=crate_relative`, `file=basename`, or `file=none`. See `FilePath`.
+//! - `output.<level>=stderr` or `output.<level>=stdout`, e.g. `output.info=stderr`.
+//! - `color.<level>=<color>`, e.g. `color.warn=#ff8800`. See `Color` for the syntax.
+//! - `style.<level>=<style>` or `message_style.<level>=<style>`, e.g. `style.error=bold red`. See
+//!   `Style` for the syntax.
+//! - A toggle, which is `+` to enable or `-` to disable, followed by `level`, `line_numbers`,
+//!   `module_path`, `hostname`, `pid`, `thread`, `timestamp`, or `compact_location`, e.g.
+//!   `+line_numbers`.
+//!
+//! The keys of the options are reserved, so e.g. `color=off` is an invalid color, not a directive
+//! for a module named `color`. Invalid entries are ignored.
+
+use super::{Color, ColorChoice, FilePath, Format, Logger, Output, Style, Theme, Timestamp};
+use filter::Directive;
+
+/// Applies a spec on top of the configuration of the logger.
+pub(crate) fn apply(mut logger: Logger, spec: &str) -> Logger {
+    for line in spec.lines() {
+        // A `#` within an entry is part of it, e.g. of the color `#ff8800`.
+        let comment = line.char_indices()
+            .find(|&(i, c)| c == '#' && line[..i].chars().next_back().map_or(true, char::is_whitespace));
+        let line = match comment {
+            Some((i, _)) => &line[..i],
+            None => line,
+        };
+        for entry in line.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            logger = apply_entry(logger, entry);
+        }
+    }
+    logger
+}
+
+fn apply_entry(logger: Logger, entry: &str) -> Logger {
+    if let Some(toggle) = entry.strip_prefix('+') {
+        return apply_toggle(logger, toggle, true);
+    }
+    if let Some(toggle) = entry.strip_prefix('-') {
+        return apply_toggle(logger, toggle, false);
+    }
+    let (key, value) = match entry.find('=') {
+        Some(i) => (entry[..i].trim(), entry[i + 1..].trim()),
+        None => return apply_directive(logger, entry),
+    };
+    if let Some(level) = key.strip_prefix("output.") {
+        let output = match value {
+            "stderr" => Output::Stderr,
+            "stdout" => Output::Stdout,
+            _ => return logger,
+        };
+        return match level.parse() {
+            Ok(l) => logger.output(&l, output),
+            Err(_) => logger,
+        };
+    }
+    if let Some(level) = key.strip_prefix("color.") {
+        return match (level.parse(), value.parse::<Color>()) {
+            (Ok(l), Ok(c)) => logger.color(&l, c),
+            _ => logger,
+        };
+    }
+    if let Some(level) = key.strip_prefix("style.") {
+        return match (level.parse(), value.parse::<Style>()) {
+            (Ok(l), Ok(s)) => logger.style(&l, s),
+            _ => logger,
+        };
+    }
+    if let Some(level) = key.strip_prefix("message_style.") {
+        return match (level.parse(), value.parse::<Style>()) {
+            (Ok(l), Ok(s)) => logger.message_style(&l, s),
+            _ => logger,
+        };
+    }
+    match (key, value) {
+        ("color", "auto") => logger.color_choice(ColorChoice::Auto),
+        ("color", "always") => logger.color_choice(ColorChoice::Always),
+        ("color", "never") => logger.color_choice(ColorChoice::Never),
+        ("format", "text") => logger.format(Format::Text),
+        ("format", "json") => logger.format(Format::Json),
+        ("format", "logfmt") => logger.format(Format::Logfmt),
+        ("file", "full") => logger.file(FilePath::Full),
+        ("file", "crate_relative") => logger.file(FilePath::CrateRelative),
+        ("file", "basename") => logger.file(FilePath::Basename),
+        ("file", "none") => logger.no_file(),
+        ("theme", name) => match Theme::named(name) {
+            Some(t) => logger.theme(&t),
+            None => logger,
+        },
+        // An invalid option is not a directive for a module with the same name.
+        ("color", _) | ("format", _) | ("file", _) => logger,
+        _ => apply_directive(logger, entry),
+    }
+}
+
+fn apply_directive(logger: Logger, entry: &str) -> Logger {
+    match entry.parse::<Directive>() {
+        Ok(d) => logger.directive(d.module(), d.level()),
+        Err(_) => logger,
+    }
+}
+
+fn apply_toggle(logger: Logger, toggle: &str, on: bool) -> Logger {
+    match toggle {
+        "level" => logger.level(on),
+        "line_numbers" => logger.line_numbers(on),
+        "module_path" => logger.module_path(on),
+        "hostname" => logger.hostname(on),
+        "pid" => logger.pid(on),
+        "thread" => logger.thread(on),
+        "compact_location" => logger.compact_location(on),
+        "timestamp" if on => {
+            let timestamp = logger.timestamp.clone().unwrap_or_else(Timestamp::rfc3339);
+            logger.timestamp(timestamp)
+        },
+        "timestamp" => logger.no_timestamp(),
+        _ => logger,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+
+    #[test]
+    fn directives_work() {
+        let logger = apply(Logger::new(), "info, myapp::db=trace,colors=debug");
+        assert_eq!(logger.directives, vec![
+            Directive::new("", LevelFilter::Info),
+            Directive::new("myapp::db", LevelFilter::Trace),
+            Directive::new("colors", LevelFilter::Debug),
+        ]);
+        let logger = apply(Logger::new(), "color=off,format=debug,theme=trace,file=info,output.info=off,style.warn=debug");
+        assert!(logger.directives.is_empty());
+    }
+
+    #[test]
+    fn options_work() {
+        let logger = apply(Logger::new(), "color=always,format=logfmt,+level,-module_path,+line_numbers,+pid,+thread,+timestamp");
+        assert_eq!(logger.color_choice, ColorChoice::Always);
+        assert_eq!(logger.format, Format::Logfmt);
+        assert!(logger.include_level);
+        assert!(!logger.include_module_path);
+        assert!(logger.include_line_numbers);
+        assert!(logger.include_pid);
+        assert!(logger.include_thread);
+        assert_eq!(logger.timestamp, Some(Timestamp::rfc3339()));
+        let logger = apply(logger, "color=never,-timestamp");
+        assert_eq!(logger.color_choice, ColorChoice::Never);
+        assert!(logger.timestamp.is_none());
+    }
+
+    #[test]
+    fn outputs_work() {
+        let logger = apply(Logger::new(), "output.error=stdout,output.INFO=stdout");
+        assert_eq!(logger.error.output, Output::Stdout);
+        assert_eq!(logger.info.output, Output::Stdout);
+        assert_eq!(logger.warn.output, Output::Stderr);
+    }
+
+    #[test]
+    fn colors_work() {
+        let logger = apply(Logger::new(), "color.error=bright-blue,color.warn=#ff8800 # orange");
+        assert_eq!(logger.error.style.foreground(), Some(Color::Fixed(12)));
+        assert_eq!(logger.warn.style.foreground(), Some(Color::Rgb(255, 136, 0)));
+    }
+
+    #[test]
+    fn themes_work() {
+        let logger = apply(Logger::new(), "theme=monochrome-bold,color.info=green");
+        assert_eq!(logger.error.style, Style::new().bold().underline());
+        assert_eq!(logger.info.style, Style::new().fg(Color::Green));
+    }
+
+    #[test]
+    fn files_work() {
+        let logger = apply(Logger::new(), "file=basename,+compact_location");
+        assert_eq!(logger.file_path, Some(FilePath::Basename));
+        assert!(logger.compact_location);
+        let logger = apply(logger, "file=none,file=sideways");
+        assert!(logger.file_path.is_none());
+    }
+
+    #[test]
+    fn styles_work() {
+        let logger = apply(Logger::new(), "style.error=bold red on black,message_style.error=italic");
+        assert_eq!(logger.error.style, Style::new().fg(Color::Red).on(Color::Black).bold());
+        assert_eq!(logger.error.message_style, Style::new().italic());
+    }
+
+    #[test]
+    fn lines_and_comments_work() {
+        let logger = apply(Logger::new(), "# The levels\ninfo # everything\n\n+level, hyper=warn\n");
+        assert_eq!(logger.directives, vec![
+            Directive::new("", LevelFilter::Info),
+            Directive::new("hyper", LevelFilter::Warn),
+        ]);
+        assert!(logger.include_level);
+    }
+
+    #[test]
+    fn invalid_entries_are_ignored() {
+        let logger = Logger::new();
+        assert_eq!(apply(logger.clone(), "loud,color=purple,format=xml,+bogus,=debug,output.loud=stdout,output.info=file,color.info=reddish,color.loud=red,style.info=blinking,theme=sepia"), logger);
+    }
+}
@@ -0,0 +1,190 @@
+//! Text styles of the tag and the message.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use ansi_term::{self, Colour};
+
+use color::{Color, ColorDepth};
+
+/// The style of text: the colors and the attributes.
+///
+/// A style is parsed from a list of words separated by whitespace: `bold`, `dimmed` (or `dim`),
+/// `italic`, `underline`, a color for the foreground, and `on` followed by a color for the
+/// background, e.g. `bold bright-red on #202020`. See `Color` for the syntax of colors. An empty
+/// string or `plain` is the plain style.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate loggerv;
+///
+/// use loggerv::{Color, Style};
+///
+/// fn main() {
+///     let style = Style::new().fg(Color::Red).on(Color::Black).bold();
+///     assert_eq!("bold red on black".parse(), Ok(style));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    /// Creates the plain style, without colors or attributes.
+    pub fn new() -> Style {
+        Style::default()
+    }
+
+    /// Sets the foreground color.
+    pub fn fg<C: Into<Color>>(mut self, c: C) -> Style {
+        self.foreground = Some(c.into());
+        self
+    }
+
+    /// Sets the background color.
+    pub fn on<C: Into<Color>>(mut self, c: C) -> Style {
+        self.background = Some(c.into());
+        self
+    }
+
+    /// Makes the text bold.
+    pub fn bold(mut self) -> Style {
+        self.bold = true;
+        self
+    }
+
+    /// Makes the text dimmed, i.e. faint.
+    pub fn dimmed(mut self) -> Style {
+        self.dimmed = true;
+        self
+    }
+
+    /// Makes the text italic.
+    pub fn italic(mut self) -> Style {
+        self.italic = true;
+        self
+    }
+
+    /// Underlines the text.
+    pub fn underline(mut self) -> Style {
+        self.underline = true;
+        self
+    }
+
+    /// Gets the foreground color.
+    pub fn foreground(&self) -> Option<Color> {
+        self.foreground
+    }
+
+    /// Gets the background color.
+    pub fn background(&self) -> Option<Color> {
+        self.background
+    }
+
+    /// Determines if the style has neither colors nor attributes.
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+
+    /// Converts the style, downgrading the colors to the ones the terminal supports.
+    pub(crate) fn to_ansi(self, depth: ColorDepth) -> ansi_term::Style {
+        let mut style = ansi_term::Style::new();
+        style.foreground = self.foreground.map(|c| c.to_colour(depth));
+        style.background = self.background.map(|c| c.to_colour(depth));
+        style.is_bold = self.bold;
+        style.is_dimmed = self.dimmed;
+        style.is_italic = self.italic;
+        style.is_underline = self.underline;
+        style
+    }
+}
+
+impl From<Color> for Style {
+    fn from(c: Color) -> Style {
+        Style::new().fg(c)
+    }
+}
+
+impl From<Colour> for Style {
+    fn from(c: Colour) -> Style {
+        Style::new().fg(c)
+    }
+}
+
+impl FromStr for Style {
+    type Err = ParseStyleError;
+
+    fn from_str(s: &str) -> Result<Style, ParseStyleError> {
+        let err = || ParseStyleError { style: String::from(s.trim()) };
+        let mut style = Style::new();
+        let mut words = s.split_whitespace();
+        while let Some(word) = words.next() {
+            style = match word.to_lowercase().as_str() {
+                "plain" => style,
+                "bold" => style.bold(),
+                "dim" | "dimmed" => style.dimmed(),
+                "italic" => style.italic(),
+                "underline" | "underlined" => style.underline(),
+                "on" => {
+                    let color = words.next().and_then(|w| w.parse::<Color>().ok()).ok_or_else(err)?;
+                    style.on(color)
+                },
+                _ if style.foreground.is_none() => style.fg(word.parse::<Color>().map_err(|_| err())?),
+                _ => return Err(err()),
+            };
+        }
+        Ok(style)
+    }
+}
+
+/// The error of parsing an invalid style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStyleError {
+    style: String,
+}
+
+impl fmt::Display for ParseStyleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid style `{}`, expected attributes and colors like `bold bright-red on black`",
+            self.style
+        )
+    }
+}
+
+impl Error for ParseStyleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_works() {
+        assert_eq!("".parse(), Ok(Style::new()));
+        assert_eq!("plain".parse(), Ok(Style::new()));
+        assert_eq!("Bold  dim italic underline".parse(), Ok(Style::new().bold().dimmed().italic().underline()));
+        assert_eq!("#ff8800 on 236".parse(), Ok(Style::new().fg(Color::Rgb(255, 136, 0)).on(Color::Fixed(236))));
+        assert_eq!("on blue".parse(), Ok(Style::new().on(Color::Blue)));
+        assert!("red blue".parse::<Style>().is_err());
+        assert!("bold on".parse::<Style>().is_err());
+        assert!("blinking".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn to_ansi_works() {
+        let style = Style::new().fg(Color::Rgb(255, 136, 0)).on(Color::Black).bold().underline();
+        assert_eq!(style.to_ansi(ColorDepth::Ansi256), Colour::Fixed(208).on(Colour::Black).bold().underline());
+        assert_eq!(Style::new().to_ansi(ColorDepth::TrueColor), ansi_term::Style::new());
+        assert!(Style::new().is_plain());
+        assert!(!Style::from(Colour::Red).is_plain());
+    }
+}
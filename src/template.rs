@@ -0,0 +1,320 @@
+//! User-defined layouts of a log statement, e.g. `"{time} {level:>5} {module}:{line} | {msg}"`.
+
+use std::fmt::Write;
+
+use log;
+
+use super::{write_thread_name, Logger, MODULE_PATH_UNKNOWN};
+use timestamp::{Elapsed, Stopwatch, Timestamp};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Time,
+    Elapsed,
+    Level,
+    Hostname,
+    Pid,
+    Thread,
+    Target,
+    Module,
+    File,
+    Line,
+    Message,
+    KeyValues,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        match name {
+            "time" => Some(Field::Time),
+            "elapsed" => Some(Field::Elapsed),
+            "level" => Some(Field::Level),
+            "hostname" => Some(Field::Hostname),
+            "pid" => Some(Field::Pid),
+            "thread" => Some(Field::Thread),
+            "target" => Some(Field::Target),
+            "module" => Some(Field::Module),
+            "file" => Some(Field::File),
+            "line" => Some(Field::Line),
+            "msg" => Some(Field::Message),
+            "kv" => Some(Field::KeyValues),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Field {
+        field: Field,
+        fill: char,
+        align: Align,
+        width: usize,
+    },
+}
+
+/// A parsed template.
+///
+/// A template is literal text with fields in braces, i.e. `{time}`, `{elapsed}`, `{level}`,
+/// `{hostname}`, `{pid}`, `{thread}`, `{target}`, `{module}`, `{file}`, `{line}`, `{msg}`, and
+/// `{kv}`, the key-value pairs like `user=42 admin=false`. A field may be followed by a colon and a
+/// padding specifier like in the `format!` macro, i.e. an optional fill character, an optional
+/// alignment (`<`, `>`, or `^`), and a minimum width, e.g. `{level:>5}` or `{module:.<20}`. Braces
+/// are written as `{{` and `}}`. An unknown field is written as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Template {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut rest = s;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let brace = &rest[i..i + 1];
+            let after = &rest[i + 1..];
+            if after.starts_with(brace) {
+                literal.push_str(brace);
+                rest = &after[1..];
+                continue;
+            }
+            if brace == "}" {
+                literal.push('}');
+                rest = after;
+                continue;
+            }
+            let field = after.find('}').and_then(|end| parse_field(&after[..end]).map(|f| (f, end)));
+            match field {
+                Some((piece, end)) => {
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(literal.clone()));
+                        literal.clear();
+                    }
+                    pieces.push(piece);
+                    rest = &after[end + 1..];
+                },
+                None => {
+                    literal.push('{');
+                    rest = after;
+                },
+            }
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Template { pieces }
+    }
+
+    /// Determines if the template writes the name of the host.
+    pub fn has_hostname(&self) -> bool {
+        self.pieces.iter().any(|p| match *p {
+            Piece::Field { field, .. } => field == Field::Hostname,
+            Piece::Literal(_) => false,
+        })
+    }
+
+    /// Appends a log statement, without a trailing newline.
+    ///
+    /// All fields except the message and the key-value pairs are styled like the tag if enabled,
+    /// the message is styled with the message style, the pairs are styled like in the text format,
+    /// and literal text is not styled.
+    pub fn render(&self, s: &mut String, logger: &Logger, record: &log::Record) {
+        let colorize = logger.should_colorize(&record.level());
+        for piece in &self.pieces {
+            match *piece {
+                Piece::Literal(ref l) => s.push_str(l),
+                Piece::Field { field, fill, align, width } => {
+                    if colorize && field == Field::KeyValues {
+                        // Each pair has its own styles, and the escape codes do not count for the
+                        // width, which is taken from the pairs without colors only if needed.
+                        let pairs = logger.key_values.collect(record);
+                        let start = s.len();
+                        logger.key_values.write_text(s, &pairs, true, logger.color_depth);
+                        trim_leading_space(s, start);
+                        if width > 0 {
+                            let mut plain = String::new();
+                            logger.key_values.write_text(&mut plain, &pairs, false, logger.color_depth);
+                            let len = plain.trim_start_matches(' ').chars().count();
+                            pad(s, start, len, fill, align, width);
+                        }
+                        continue;
+                    }
+                    let style = if !colorize {
+                        None
+                    } else if field == Field::Message {
+                        Some(logger.select_message_style(&record.level()))
+                    } else {
+                        Some(logger.select_style(&record.level()))
+                    };
+                    if let Some(style) = style {
+                        write!(s, "{}", style.prefix()).expect("Writing to a string");
+                    }
+                    let start = s.len();
+                    write_field(s, logger, record, field);
+                    if width > 0 {
+                        let len = s[start..].chars().count();
+                        pad(s, start, len, fill, align, width);
+                    }
+                    if let Some(style) = style {
+                        write!(s, "{}", style.suffix()).expect("Writing to a string");
+                    }
+                },
+            }
+        }
+    }
+}
+
+fn parse_field(s: &str) -> Option<Piece> {
+    let (name, spec) = match s.find(':') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, ""),
+    };
+    let field = Field::from_name(name)?;
+    let mut chars = spec.chars();
+    let (fill, align, width) = match (chars.next(), chars.next()) {
+        (Some(f), Some(a)) if parse_align(a).is_some() => (f, parse_align(a), chars.as_str()),
+        (Some(a), _) if parse_align(a).is_some() => (' ', parse_align(a), &spec[a.len_utf8()..]),
+        _ => (' ', None, spec),
+    };
+    let width = if width.is_empty() { 0 } else { width.parse().ok()? };
+    Some(Piece::Field {
+        field,
+        fill,
+        align: align.unwrap_or(Align::Left),
+        width,
+    })
+}
+
+fn parse_align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    }
+}
+
+fn write_field(s: &mut String, logger: &Logger, record: &log::Record, field: Field) {
+    match field {
+        Field::Time => match logger.timestamp {
+            Some(ref t) => t.write_now(s),
+            None => Timestamp::rfc3339().write_now(s),
+        },
+        Field::Elapsed => {
+            let e = logger.elapsed.unwrap_or(Elapsed::SinceStart);
+            Stopwatch::write(s, logger.stopwatch.lap(e));
+        },
+        Field::Level => s.push_str(record.level().as_str()),
+        Field::Hostname => s.push_str(logger.resolved_hostname()),
+        Field::Pid => write!(s, "{}", ::std::process::id()).expect("Writing to a string"),
+        Field::Thread => write_thread_name(s),
+        Field::Target => s.push_str(record.target()),
+        Field::Module => s.push_str(record.module_path().unwrap_or(MODULE_PATH_UNKNOWN)),
+        Field::File => match logger.file_path {
+            Some(p) => s.push_str(p.trim(record.file().unwrap_or(""))),
+            None => s.push_str(record.file().unwrap_or("")),
+        },
+        Field::Line => {
+            if let Some(l) = record.line() {
+                write!(s, "{}", l).expect("Writing to a string");
+            }
+        },
+        Field::Message => write!(s, "{}", record.args()).expect("Writing to a string"),
+        Field::KeyValues => {
+            let start = s.len();
+            logger.key_values.write_text(s, &logger.key_values.collect(record), false, logger.color_depth);
+            trim_leading_space(s, start);
+        },
+    }
+}
+
+/// Removes the space that `KeyValues::write_text` writes before the first pair.
+fn trim_leading_space(s: &mut String, start: usize) {
+    if s[start..].starts_with(' ') {
+        s.remove(start);
+    }
+}
+
+/// Pads the value that was written from the index on to the width, where the number of
+/// characters excludes escape codes if it is styled.
+fn pad(s: &mut String, start: usize, len: usize, fill: char, align: Align, width: usize) {
+    if len >= width {
+        return;
+    }
+    let padding = width - len;
+    let (before, after) = match align {
+        Align::Left => (0, padding),
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+    for _ in 0..before {
+        s.insert(start, fill);
+    }
+    s.extend(::std::iter::repeat(fill).take(after));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_with(logger: &Logger, template: &str) -> String {
+        let kvs: [(&str, log::kv::Value); 2] = [("retries", log::kv::Value::from(3)), ("peer", log::kv::Value::from("db"))];
+        let record = log::Record::builder()
+            .level(log::Level::Warn)
+            .module_path(Some("app::net"))
+            .line(Some(42))
+            .args(format_args!("connection lost"))
+            .key_values(&kvs)
+            .build();
+        let mut s = String::new();
+        Template::parse(template).render(&mut s, logger, &record);
+        s
+    }
+
+    fn render(template: &str) -> String {
+        render_with(&Logger::new().no_colors(), template)
+    }
+
+    #[test]
+    fn fields_work() {
+        assert_eq!(render("{level} {module}:{line} | {msg}"), "WARN app::net:42 | connection lost");
+        assert_eq!(render("{thread}"), ::std::thread::current().name().unwrap());
+        assert_eq!(render("{pid}"), ::std::process::id().to_string());
+    }
+
+    #[test]
+    fn key_values_work() {
+        assert_eq!(render("{msg} [{kv:<22}]"), "connection lost [retries=3 peer=db     ]");
+        let logger = Logger::new()
+            .color_choice(::ColorChoice::Always)
+            .key_values(::KeyValues::new().hide("peer").key_style(::Style::new().bold()));
+        assert_eq!(render_with(&logger, "[{kv:>11}]"), "[  \u{1b}[1mretries\u{1b}[0m=3]");
+        assert_eq!(render_with(&logger, "[{kv}]"), "[\u{1b}[1mretries\u{1b}[0m=3]");
+    }
+
+    #[test]
+    fn padding_works() {
+        assert_eq!(render("[{level:>5}]"), "[ WARN]");
+        assert_eq!(render("[{level:6}]"), "[WARN  ]");
+        assert_eq!(render("[{level:*^8}]"), "[**WARN**]");
+        assert_eq!(render("[{module:3}]"), "[app::net]");
+    }
+
+    #[test]
+    fn literals_work() {
+        assert_eq!(render("{{{level}}}"), "{WARN}");
+        assert_eq!(render("{unknown} {level"), "{unknown} {level");
+        assert_eq!(render("{level:x} }"), "{level:x} }");
+    }
+}
@@ -0,0 +1,14 @@
+//! Helpers for the tests of several modules.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+/// Gets the path of a file in an empty directory that is unique to the test and the process.
+pub(crate) fn temp_path(test: &str, file: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("loggerv-{}-{}", test, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(file)
+}
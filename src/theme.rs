@@ -0,0 +1,202 @@
+//! Sets of level styles, with named presets and a registry of custom themes.
+
+use std::sync::{Mutex, MutexGuard};
+
+use log;
+
+use color::Color;
+use style::Style;
+
+/// The names of the built-in themes, in the order of `Theme::names`.
+const PRESETS: [&str; 5] = ["default", "solarized", "monochrome-bold", "high-contrast", "light-background"];
+
+/// The registered themes, which take precedence over the built-in ones with the same name.
+static REGISTRY: Mutex<Vec<(String, Theme)>> = Mutex::new(Vec::new());
+
+/// The styles of the tag and the message for all five levels.
+///
+/// A theme is either built, looked up by name, or both, i.e. a named theme with some styles
+/// changed. The built-in themes are:
+///
+/// | Name               | Description                                                      |
+/// |--------------------|------------------------------------------------------------------|
+/// | `default`          | The default colors, i.e. bright red, yellow, and green, and greys |
+/// | `solarized`        | The accent colors of the Solarized palette                       |
+/// | `monochrome-bold`  | No colors, only bold, underlined, and dimmed text                |
+/// | `high-contrast`    | Bold text, with a background for errors and warnings             |
+/// | `light-background` | Dark colors that are readable on a light background              |
+///
+/// Custom themes are registered with `Theme::register` and then found by name, e.g. from the
+/// `theme` entry of an environment variable or a config file.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use log::Level;
+/// use loggerv::{Color, Theme};
+///
+/// fn main() {
+///     let ocean = Theme::named("solarized").unwrap()
+///         .style(&Level::Info, Color::Rgb(0x2a, 0xa1, 0x98));
+///     Theme::register("ocean", ocean);
+///
+///     loggerv::Logger::new()
+///         .theme(&Theme::named("ocean").unwrap())
+///         .init()
+///         .unwrap();
+///
+///     error!("This is printed in the red of Solarized");
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Theme {
+    styles: [Style; 5],
+    message_styles: [Style; 5],
+}
+
+impl Theme {
+    /// Creates a theme with the plain style for all levels.
+    pub fn new() -> Theme {
+        Theme::default()
+    }
+
+    /// Sets the style of the tag for a level. See `Logger::style`.
+    pub fn style<S: Into<Style>>(mut self, l: &log::Level, s: S) -> Theme {
+        self.styles[index(l)] = s.into();
+        self
+    }
+
+    /// Sets the style of the message for a level. See `Logger::message_style`.
+    pub fn message_style<S: Into<Style>>(mut self, l: &log::Level, s: S) -> Theme {
+        self.message_styles[index(l)] = s.into();
+        self
+    }
+
+    /// Gets the style of the tag for a level.
+    pub fn style_for(&self, l: &log::Level) -> Style {
+        self.styles[index(l)]
+    }
+
+    /// Gets the style of the message for a level.
+    pub fn message_style_for(&self, l: &log::Level) -> Style {
+        self.message_styles[index(l)]
+    }
+
+    /// Finds a registered or built-in theme by name, ignoring case.
+    pub fn named(name: &str) -> Option<Theme> {
+        let name = name.trim().to_lowercase();
+        let registered = registry().iter().find(|t| t.0 == name).map(|t| t.1);
+        registered.or_else(|| preset(&name))
+    }
+
+    /// Registers a custom theme, which replaces a registered or built-in theme with the same name.
+    pub fn register<S: Into<String>>(name: S, theme: Theme) {
+        let name = name.into().trim().to_lowercase();
+        let mut themes = registry();
+        themes.retain(|t| t.0 != name);
+        themes.push((name, theme));
+    }
+
+    /// Gets the names of the built-in and registered themes.
+    pub fn names() -> Vec<String> {
+        let mut names: Vec<String> = PRESETS.iter().map(|n| String::from(*n)).collect();
+        for (name, _) in registry().iter() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+}
+
+fn index(l: &log::Level) -> usize {
+    *l as usize - 1
+}
+
+fn registry() -> MutexGuard<'static, Vec<(String, Theme)>> {
+    // The registry is only changed by pushing or removing whole entries, so a poisoned lock is
+    // still consistent.
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Creates a theme from the tag styles of the levels, from ERROR to TRACE.
+fn from_styles(styles: [Style; 5]) -> Theme {
+    Theme {
+        styles,
+        message_styles: [Style::new(); 5],
+    }
+}
+
+fn preset(name: &str) -> Option<Theme> {
+    let theme = match name {
+        "default" => from_styles([
+            Style::from(::DEFAULT_ERROR_COLOR),
+            Style::from(::DEFAULT_WARN_COLOR),
+            Style::from(::DEFAULT_INFO_COLOR),
+            Style::from(::DEFAULT_DEBUG_COLOR),
+            Style::from(::DEFAULT_TRACE_COLOR),
+        ]),
+        "solarized" => from_styles([
+            Style::new().fg(Color::Rgb(0xdc, 0x32, 0x2f)),
+            Style::new().fg(Color::Rgb(0xb5, 0x89, 0x00)),
+            Style::new().fg(Color::Rgb(0x85, 0x99, 0x00)),
+            Style::new().fg(Color::Rgb(0x26, 0x8b, 0xd2)),
+            Style::new().fg(Color::Rgb(0x93, 0xa1, 0xa1)),
+        ]),
+        "monochrome-bold" => from_styles([
+            Style::new().bold().underline(),
+            Style::new().bold(),
+            Style::new(),
+            Style::new().dimmed(),
+            Style::new().dimmed().italic(),
+        ]),
+        "high-contrast" => from_styles([
+            Style::new().fg(Color::Fixed(15)).on(Color::Red).bold(),
+            Style::new().fg(Color::Black).on(Color::Fixed(11)).bold(),
+            Style::new().fg(Color::Fixed(10)).bold(),
+            Style::new().fg(Color::Fixed(15)),
+            Style::new().fg(Color::Fixed(14)),
+        ]),
+        "light-background" => from_styles([
+            Style::new().fg(Color::Fixed(124)),
+            Style::new().fg(Color::Fixed(130)),
+            Style::new().fg(Color::Fixed(28)),
+            Style::new().fg(Color::Fixed(25)),
+            Style::new().fg(Color::Fixed(242)),
+        ]),
+        _ => return None,
+    };
+    Some(theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_work() {
+        for name in &PRESETS {
+            assert!(Theme::named(name).is_some(), "{}", name);
+        }
+        let default = Theme::named(" Default ").unwrap();
+        assert_eq!(default.style_for(&log::Level::Error), Style::from(::DEFAULT_ERROR_COLOR));
+        assert_eq!(default.style_for(&log::Level::Trace), Style::from(::DEFAULT_TRACE_COLOR));
+        assert!(default.message_style_for(&log::Level::Error).is_plain());
+        assert!(Theme::named("sepia").is_none());
+    }
+
+    #[test]
+    fn register_works() {
+        let theme = Theme::new().style(&log::Level::Warn, Style::new().bold());
+        Theme::register("Test-Register", theme);
+        assert_eq!(Theme::named("test-register"), Some(theme));
+        assert!(Theme::names().contains(&String::from("test-register")));
+        let theme = theme.message_style(&log::Level::Warn, Color::Red);
+        Theme::register("test-register", theme);
+        assert_eq!(Theme::named("test-register"), Some(theme));
+        assert_eq!(Theme::names().iter().filter(|n| *n == "test-register").count(), 1);
+    }
+}
@@ -0,0 +1,282 @@
+//! Wall-clock timestamps and elapsed times for the tag portion of a log statement.
+
+use std::fmt::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, Offset, TimeZone, Utc};
+
+/// The clock used to read the current time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clock {
+    Utc,
+    Local,
+}
+
+/// The number of sub-second digits written for the built-in formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+/// The layout of a timestamp.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// A full date and time, e.g. `2018-03-01T14:05:09.123+01:00`, or with a `Z` for UTC.
+    Rfc3339,
+    /// Only the time of day, e.g. `14:05:09.123`.
+    Time,
+    /// A `strftime`-like pattern, e.g. `%Y-%m-%d %H:%M:%S%.3f`.
+    ///
+    /// See the [chrono](https://docs.rs/chrono/0.4/chrono/format/strftime/index.html)
+    /// documentation for the supported specifiers. The precision is ignored; use `%.3f`, `%.6f`,
+    /// or `%.9f` instead.
+    Custom(String),
+}
+
+/// The configuration of the timestamp in the tag portion of a log statement.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use loggerv::{Precision, Timestamp};
+///
+/// fn main() {
+///     loggerv::Logger::new()
+///         .timestamp(Timestamp::time().local().precision(Precision::Millis))
+///         .init()
+///         .unwrap();
+///
+///     error!("This is printed with the local time of day, e.g. 14:05:09.123");
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timestamp {
+    clock: Clock,
+    format: TimestampFormat,
+    precision: Precision,
+}
+
+impl Timestamp {
+    /// Creates a UTC timestamp with the format and a precision of seconds.
+    pub fn new(format: TimestampFormat) -> Timestamp {
+        Timestamp {
+            clock: Clock::Utc,
+            format,
+            precision: Precision::Seconds,
+        }
+    }
+
+    /// Creates a UTC timestamp with a full RFC 3339 date and time.
+    pub fn rfc3339() -> Timestamp {
+        Timestamp::new(TimestampFormat::Rfc3339)
+    }
+
+    /// Creates a UTC timestamp with only the time of day.
+    pub fn time() -> Timestamp {
+        Timestamp::new(TimestampFormat::Time)
+    }
+
+    /// Creates a UTC timestamp with a `strftime`-like pattern.
+    pub fn custom(pattern: &str) -> Timestamp {
+        Timestamp::new(TimestampFormat::Custom(String::from(pattern)))
+    }
+
+    /// Sets the clock.
+    pub fn clock(mut self, c: Clock) -> Self {
+        self.clock = c;
+        self
+    }
+
+    /// Uses the UTC clock. This is the default.
+    pub fn utc(self) -> Self {
+        self.clock(Clock::Utc)
+    }
+
+    /// Uses the local clock, with the offset of the local time zone.
+    pub fn local(self) -> Self {
+        self.clock(Clock::Local)
+    }
+
+    /// Sets the number of sub-second digits for the built-in formats.
+    pub fn precision(mut self, p: Precision) -> Self {
+        self.precision = p;
+        self
+    }
+
+    /// Renders the current time.
+    pub fn now(&self) -> String {
+        let mut s = String::with_capacity(32);
+        self.write_now(&mut s);
+        s
+    }
+
+    /// Appends the rendered current time to the string.
+    pub(crate) fn write_now(&self, s: &mut String) {
+        match self.clock {
+            Clock::Utc => self.write(s, &Utc::now()),
+            Clock::Local => self.write(s, &Local::now()),
+        }
+    }
+
+    /// Renders the time.
+    pub fn render<Tz: TimeZone>(&self, t: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        let mut s = String::with_capacity(32);
+        self.write(&mut s, t);
+        s
+    }
+
+    /// Appends the rendered time to the string.
+    fn write<Tz: TimeZone>(&self, s: &mut String, t: &DateTime<Tz>)
+    where
+        Tz::Offset: fmt::Display,
+    {
+        match self.format {
+            TimestampFormat::Rfc3339 => {
+                let pattern = match self.precision {
+                    Precision::Seconds => "%Y-%m-%dT%H:%M:%S",
+                    Precision::Millis => "%Y-%m-%dT%H:%M:%S%.3f",
+                    Precision::Micros => "%Y-%m-%dT%H:%M:%S%.6f",
+                    Precision::Nanos => "%Y-%m-%dT%H:%M:%S%.9f",
+                };
+                write!(s, "{}", t.format(pattern)).expect("Writing to a string");
+                // Like `DateTime::to_rfc3339_opts`, UTC is written as `Z`.
+                if t.offset().fix().local_minus_utc() == 0 {
+                    s.push('Z');
+                } else {
+                    write!(s, "{}", t.format("%:z")).expect("Writing to a string");
+                }
+            },
+            TimestampFormat::Time => {
+                let pattern = match self.precision {
+                    Precision::Seconds => "%H:%M:%S",
+                    Precision::Millis => "%H:%M:%S%.3f",
+                    Precision::Micros => "%H:%M:%S%.6f",
+                    Precision::Nanos => "%H:%M:%S%.9f",
+                };
+                write!(s, "{}", t.format(pattern)).expect("Writing to a string");
+            },
+            TimestampFormat::Custom(ref pattern) => {
+                // An invalid pattern fails while formatting, which would panic with `to_string`,
+                // so the pattern itself is written instead to make the mistake visible.
+                let start = s.len();
+                if write!(s, "{}", t.format(pattern)).is_err() {
+                    s.truncate(start);
+                    s.push_str(pattern);
+                }
+            },
+        }
+    }
+}
+
+/// The reference point of the elapsed time in the tag portion of a log statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Elapsed {
+    /// The monotonic time since the logger was initialized.
+    SinceStart,
+    /// The monotonic time since the previous log statement, or since the logger was initialized
+    /// for the first log statement.
+    SincePrevious,
+}
+
+/// Measures the elapsed time since initialization and since the previous log statement.
+///
+/// Clones share the time of the previous log statement.
+#[derive(Clone)]
+pub(crate) struct Stopwatch {
+    start: Instant,
+    previous: Arc<AtomicU64>,
+}
+
+impl Stopwatch {
+    pub fn new() -> Stopwatch {
+        Stopwatch {
+            start: Instant::now(),
+            previous: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Gets the elapsed time and marks now as the time of the previous log statement.
+    pub fn lap(&self, e: Elapsed) -> Duration {
+        let now = self.start.elapsed();
+        let nanos = now.as_secs() * 1_000_000_000 + u64::from(now.subsec_nanos());
+        let previous = self.previous.swap(nanos, Ordering::Relaxed);
+        match e {
+            Elapsed::SinceStart => now,
+            Elapsed::SincePrevious => Duration::from_nanos(nanos.saturating_sub(previous)),
+        }
+    }
+
+    /// Appends the elapsed time with millisecond precision, e.g. `+0.153s`.
+    pub fn write(s: &mut String, d: Duration) {
+        write!(s, "+{}.{:03}s", d.as_secs(), d.subsec_millis()).expect("Writing to a string");
+    }
+}
+
+impl fmt::Debug for Stopwatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Stopwatch").field("start", &self.start).finish()
+    }
+}
+
+impl PartialEq for Stopwatch {
+    fn eq(&self, other: &Stopwatch) -> bool {
+        self.start == other.start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, SecondsFormat};
+
+    #[test]
+    fn rfc3339_works() {
+        let t = Utc.with_ymd_and_hms(2018, 3, 1, 14, 5, 9).unwrap();
+        assert_eq!(Timestamp::rfc3339().render(&t), "2018-03-01T14:05:09Z");
+        let t = FixedOffset::east_opt(3600).unwrap().with_ymd_and_hms(2018, 3, 1, 14, 5, 9).unwrap();
+        assert_eq!(Timestamp::rfc3339().precision(Precision::Millis).render(&t), "2018-03-01T14:05:09.000+01:00");
+        let t = FixedOffset::west_opt(5400).unwrap().with_ymd_and_hms(2018, 3, 1, 14, 5, 9).unwrap();
+        assert_eq!(Timestamp::rfc3339().precision(Precision::Nanos).render(&t), t.to_rfc3339_opts(SecondsFormat::Nanos, true));
+    }
+
+    #[test]
+    fn time_works() {
+        let t = Utc.with_ymd_and_hms(2018, 3, 1, 14, 5, 9).unwrap();
+        assert_eq!(Timestamp::time().render(&t), "14:05:09");
+        assert_eq!(Timestamp::time().precision(Precision::Micros).render(&t), "14:05:09.000000");
+    }
+
+    #[test]
+    fn custom_works() {
+        let t = Utc.with_ymd_and_hms(2018, 3, 1, 14, 5, 9).unwrap();
+        assert_eq!(Timestamp::custom("%d/%m/%Y %H:%M").render(&t), "01/03/2018 14:05");
+        assert_eq!(Timestamp::custom("%Q").render(&t), "%Q");
+    }
+
+    #[test]
+    fn stopwatch_works() {
+        let stopwatch = Stopwatch::new();
+        let first = stopwatch.lap(Elapsed::SinceStart);
+        ::std::thread::sleep(Duration::from_millis(5));
+        assert!(stopwatch.lap(Elapsed::SincePrevious) >= Duration::from_millis(5));
+        let second = stopwatch.lap(Elapsed::SincePrevious);
+        // The time since the start includes the sleep, the time since the previous lap does not.
+        let total = stopwatch.lap(Elapsed::SinceStart);
+        assert!(total > first);
+        assert!(total >= second + Duration::from_millis(5));
+        let mut s = String::new();
+        Stopwatch::write(&mut s, Duration::from_millis(1153));
+        assert_eq!(s, "+1.153s");
+    }
+}
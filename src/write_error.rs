@@ -0,0 +1,219 @@
+//! Handling errors of writing the log statements.
+
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::Output;
+
+type Callback = Arc<dyn Fn(&Output, &io::Error) + Send + Sync>;
+
+/// What to do when a log statement cannot be written to its output.
+///
+/// A broken pipe, e.g. when piping into `head`, which exits after the first lines, is treated like
+/// any other error, except with `Panic`, where it is ignored.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate log;
+/// extern crate loggerv;
+///
+/// use log::Level;
+/// use loggerv::{OnWriteError, Output};
+///
+/// fn main() {
+///     loggerv::Logger::new()
+///         .output(&Level::Info, Output::Stdout)
+///         .on_write_error(OnWriteError::Fallback(Output::Stderr))
+///         .verbosity(1)
+///         .init()
+///         .unwrap();
+///
+///     info!("This is printed to stderr if stdout is closed");
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub enum OnWriteError {
+    /// Panic, except for a broken pipe, which is ignored. This is the default.
+    ///
+    /// On the background thread, see `Background`, there is no caller to panic, so a notice with
+    /// the first error is written to stderr instead, and the log statements are dropped.
+    #[default]
+    Panic,
+    /// Ignore the error and keep writing the following log statements.
+    Ignore,
+    /// Stop writing log statements after the first error.
+    Silence,
+    /// Write the log statement to another output instead, ignoring errors of that output.
+    Fallback(Output),
+    /// Call a function with the output and the error, e.g. to count the errors or to show them in
+    /// a user interface. The function must not log, since the logger may be locked.
+    Callback(Callback),
+}
+
+impl OnWriteError {
+    /// Creates the policy that calls the function with the output and the error.
+    pub fn callback<F>(f: F) -> OnWriteError
+    where
+        F: Fn(&Output, &io::Error) + Send + Sync + 'static,
+    {
+        OnWriteError::Callback(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for OnWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OnWriteError::Panic => f.write_str("Panic"),
+            OnWriteError::Ignore => f.write_str("Ignore"),
+            OnWriteError::Silence => f.write_str("Silence"),
+            OnWriteError::Fallback(ref o) => f.debug_tuple("Fallback").field(o).finish(),
+            OnWriteError::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
+impl PartialEq for OnWriteError {
+    fn eq(&self, other: &OnWriteError) -> bool {
+        match (self, other) {
+            (OnWriteError::Panic, OnWriteError::Panic) => true,
+            (OnWriteError::Ignore, OnWriteError::Ignore) => true,
+            (OnWriteError::Silence, OnWriteError::Silence) => true,
+            (OnWriteError::Fallback(a), OnWriteError::Fallback(b)) => a == b,
+            (OnWriteError::Callback(a), OnWriteError::Callback(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// The policy together with the state it needs, which is shared by the clones of a logger.
+#[derive(Debug, Clone)]
+pub(crate) struct WriteErrors {
+    policy: OnWriteError,
+    silenced: Arc<AtomicBool>,
+    /// If an error on the background thread has been reported.
+    reported: Arc<AtomicBool>,
+}
+
+impl WriteErrors {
+    pub(crate) fn new(policy: OnWriteError) -> WriteErrors {
+        WriteErrors {
+            policy,
+            silenced: Arc::new(AtomicBool::new(false)),
+            reported: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Writes a whole log statement to the output and handles an error according to the policy.
+    ///
+    /// On the background thread, there is no caller to panic, so `Panic` reports the first error
+    /// on stderr and ignores the rest.
+    pub(crate) fn write(&self, output: &Output, buf: &[u8], background: bool) {
+        if self.silenced.load(Ordering::Relaxed) {
+            return;
+        }
+        let e = match output.write_all(buf) {
+            Ok(()) => return,
+            Err(e) => e,
+        };
+        match self.policy {
+            OnWriteError::Panic if e.kind() == io::ErrorKind::BrokenPipe => {},
+            OnWriteError::Panic if background => {
+                if !self.reported.swap(true, Ordering::Relaxed) {
+                    let notice = format!("loggerv: dropping log statements because writing failed: {}\n", e);
+                    let _ = Output::Stderr.write_all(notice.as_bytes());
+                }
+            },
+            OnWriteError::Panic => match *output {
+                Output::Stderr => panic!("Writing to stderr: {}", e),
+                Output::Stdout => panic!("Writing to stdout: {}", e),
+                Output::Writer(_) => panic!("Writing to output: {}", e),
+            },
+            OnWriteError::Ignore => {},
+            OnWriteError::Silence => self.silenced.store(true, Ordering::Relaxed),
+            OnWriteError::Fallback(ref fallback) => {
+                let _ = fallback.write_all(buf);
+            },
+            OnWriteError::Callback(ref f) => f(output, &e),
+        }
+    }
+}
+
+impl PartialEq for WriteErrors {
+    fn eq(&self, other: &WriteErrors) -> bool {
+        self.policy == other.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::Mutex;
+    use std::sync::atomic::AtomicUsize;
+
+    struct Failing(io::ErrorKind);
+
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(self.0, "failing"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn broken_pipes_are_ignored() {
+        WriteErrors::new(OnWriteError::Panic).write(&Output::writer(Failing(io::ErrorKind::BrokenPipe)), b"a\n", false);
+    }
+
+    #[test]
+    #[should_panic(expected = "Writing to output: failing")]
+    fn other_errors_panic() {
+        WriteErrors::new(OnWriteError::Panic).write(&Output::writer(Failing(io::ErrorKind::Other)), b"a\n", false);
+    }
+
+    #[test]
+    fn background_errors_are_reported_once() {
+        let errors = WriteErrors::new(OnWriteError::Panic);
+        let failing = Output::writer(Failing(io::ErrorKind::Other));
+        errors.write(&failing, b"a\n", true);
+        assert!(errors.reported.load(Ordering::Relaxed));
+        errors.write(&failing, b"b\n", true);
+    }
+
+    #[test]
+    fn callback_works() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let errors = {
+            let count = count.clone();
+            WriteErrors::new(OnWriteError::callback(move |_, _| { count.fetch_add(1, Ordering::SeqCst); }))
+        };
+        let failing = Output::writer(Failing(io::ErrorKind::Other));
+        errors.write(&failing, b"a\n", false);
+        errors.write(&failing, b"b\n", false);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn silence_works() {
+        let failing = Output::writer(Failing(io::ErrorKind::Other));
+        let errors = WriteErrors::new(OnWriteError::Silence);
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        errors.write(&failing, b"a\n", false);
+        errors.clone().write(&Output::Writer(buffer.clone()), b"b\n", false);
+        assert!(buffer.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn fallback_works() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let errors = WriteErrors::new(OnWriteError::Fallback(Output::Writer(buffer.clone())));
+        errors.write(&Output::writer(Failing(io::ErrorKind::Other)), b"a\n", false);
+        assert_eq!(&buffer.lock().unwrap()[..], b"a\n");
+    }
+}